@@ -4,14 +4,33 @@ use crate::*;
 pub fn register() -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bool {
     tauri::generate_handler![
         setup::spawn_pty, pty::io::write_to_pty, pty::io::write_to_all_ptys,
-        pty::management::resize_pty, pty::management::close_pty, pty::management::set_pty_visibility,
-        pty::io::get_pty_buffer, pty::io::clear_pty_buffer, fs::scanning::scan_project, fs::scanning::scan_project_streamed,
-        fs::scanning::index_images, fs::operations::rename_entry, fs::operations::delete_entry, fs::operations::log_to_file,
+        pty::management::resize_pty, pty::signals::close_pty, pty::management::set_pty_visibility,
+        pty::management::list_pty_sessions, pty::scrollback::search_pty_scrollback, pty::scrollback::get_scrollback_limit,
+        pty::scrollback::set_scrollback_limit, pty::recording::start_pty_recording, pty::recording::stop_pty_recording,
+        pty::recording::replay_pty_recording, pty::procinfo::pty_process_info,
+        pty::signals::signal_pty, pty::stream::attach_pty_stream, pty::stream::detach_pty_stream, pty::stream::ack_pty_data,
+        pty::broadcast::create_broadcast_group, pty::broadcast::list_broadcast_groups, pty::broadcast::update_broadcast_group,
+        pty::broadcast::delete_broadcast_group, pty::broadcast::broadcast_to_group,
+        pty::expect::pty_expect, pty::expect::pty_run,
+        pty::io::get_pty_buffer, pty::io::clear_pty_buffer, pty::io::get_pty_commands, pty::profiles::list_shell_profiles,
+        pty::profiles::save_shell_profile, pty::profiles::delete_shell_profile, fs::scanning::scan_project, fs::scanning::scan_project_streamed,
+        fs::scanning::index_images, fs::operations::rename_entry, fs::operations::delete_entry, fs::trash::list_trash, fs::trash::restore_entry, fs::trash::empty_trash,
+        fs::operations::log_to_file,
         fs::operations::read_text_file, fs::operations::write_text_file, fs::create_dir, fs::get_file_size,
-        search::commands::search_in_projects, watcher::commands::watch_project, watcher::folders::watch_folder,
+        fs::journal::undo_fs_op, fs::journal::redo_fs_op, fs::journal::list_fs_ops,
+        fs::transfer::copy_entries, fs::transfer::move_entries, fs::transfer::duplicate_entry, fs::transfer::cancel_transfer, fs::transfer::resolve_transfer_conflict,
+        search::commands::search_in_projects, search::commands::start_search, search::commands::cancel_search,
+        search::replace::preview_replace, search::replace::apply_replace, search::replace::undo_replace,
+        search::structural::structural_search, search::structural::preview_structural_rewrite, search::structural::apply_structural_rewrite,
+        search::saved::list_search_history, search::saved::clear_search_history, search::saved::save_search, search::saved::list_saved_searches,
+        search::saved::delete_saved_search, search::saved::run_saved_search, search::saved::watch_saved_search, search::saved::unwatch_saved_search,
+        watcher::commands::watch_project, watcher::folders::watch_folder,
         watcher::folders::unwatch_folder, watcher::commands::unwatch_project, workspace::save_workspace,
+        symbols::commands::index_symbols, symbols::commands::document_symbols, symbols::commands::workspace_symbols,
+        finder::commands::find_files, finder::commands::mark_file_opened,
         db::list_folder_from_db, db::index_project_db, diff::commands::sync_dir,
         binary::commands::scan_project_binary, mmap_viewer::read_file_mmap,
+        index::commands::build_content_index, index::commands::content_index_status, index::commands::drop_content_index,
         lsm::commands::index_project_lsm, lsm::commands::list_folder_lsm, plugin_system::execute_wasm_plugin
     ]
 }
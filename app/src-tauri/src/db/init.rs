@@ -10,5 +10,19 @@ pub fn init_db(app_dir: &std::path::Path) -> Result<Connection> {
     conn.execute("CREATE TABLE IF NOT EXISTS files (id INTEGER PRIMARY KEY, project_id TEXT NOT NULL, parent_path TEXT, name TEXT NOT NULL, path TEXT NOT NULL UNIQUE, is_folder INTEGER NOT NULL)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_parent ON files (parent_path)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_project ON files (project_id)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS symbols (id INTEGER PRIMARY KEY, project_id TEXT NOT NULL, file_path TEXT NOT NULL, name TEXT NOT NULL, kind TEXT NOT NULL, container TEXT, start_line INTEGER NOT NULL, start_col INTEGER NOT NULL, end_line INTEGER NOT NULL, end_col INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_symbols_file ON symbols (file_path)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_symbols_name ON symbols (name COLLATE NOCASE)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS symbol_files (path TEXT PRIMARY KEY, project_id TEXT NOT NULL, mtime INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS recent_files (path TEXT PRIMARY KEY, project_id TEXT NOT NULL, opened_at INTEGER NOT NULL, count INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_recent_project ON recent_files (project_id)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS shell_profiles (id TEXT PRIMARY KEY, name TEXT NOT NULL, program TEXT NOT NULL, args TEXT NOT NULL, env TEXT NOT NULL, login INTEGER NOT NULL, prompt TEXT)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS replace_batches (id TEXT PRIMARY KEY, created_at INTEGER NOT NULL, files TEXT NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS search_history (id INTEGER PRIMARY KEY, query TEXT NOT NULL, options TEXT NOT NULL, paths TEXT NOT NULL, searched_at INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS saved_searches (name TEXT PRIMARY KEY, query TEXT NOT NULL, options TEXT NOT NULL, paths TEXT NOT NULL, created_at INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS fs_journal (id INTEGER PRIMARY KEY, op TEXT NOT NULL, created_at INTEGER NOT NULL, undone INTEGER NOT NULL, stale INTEGER NOT NULL DEFAULT 0)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS trash_entries (id TEXT PRIMARY KEY, trashed_at INTEGER NOT NULL)", [])?;
+    conn.execute("CREATE TABLE IF NOT EXISTS broadcast_groups (name TEXT PRIMARY KEY, members TEXT NOT NULL)", [])?;
     Ok(conn)
 }
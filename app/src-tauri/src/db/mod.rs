@@ -1,9 +1,11 @@
 
 pub mod init;
 pub mod indexing;
+pub mod settings;
 
 pub use init::init_db;
 pub use indexing::index_project_in_db;
+pub use settings::{get_setting, set_setting};
 
 use rusqlite::{params, Connection, Result};
 use crate::fs::FileNode;
@@ -0,0 +1,11 @@
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub fn get_setting(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?", params![key], |r| r.get(0)).optional().ok().flatten()
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)", params![key, value]).map_err(|e| e.to_string())?;
+    Ok(())
+}
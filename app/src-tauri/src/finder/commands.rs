@@ -0,0 +1,91 @@
+
+use crate::finder::fuzzy::fuzzy_match;
+use crate::fs::FileNode;
+use crate::state::AppState;
+use bincode::{config, decode_from_slice};
+use rayon::prelude::*;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+
+const DEFAULT_LIMIT: usize = 50;
+const RECENT_KEEP: usize = 500;
+/// Score added for a file opened just now; halves every day.
+const RECENT_BOOST: f64 = 48.0;
+
+#[derive(Serialize)]
+pub struct FileMatch {
+    pub path: String,
+    /// Path relative to the project; `positions` index its chars.
+    pub relative: String,
+    pub score: i32,
+    pub positions: Vec<usize>,
+    pub recent: bool,
+}
+
+/// Files of a project from the SQLite index, falling back to the sled `meta` tree.
+fn project_files(state: &AppState, project: &str) -> Result<Vec<String>, String> {
+    let files: Vec<String> = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        let mut st = conn.prepare("SELECT path FROM files WHERE project_id = ? AND is_folder = 0").map_err(|e| e.to_string())?;
+        let rows = st.query_map(params![project], |r| r.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+    if !files.is_empty() { return Ok(files); }
+    let Some(db) = state.lsm_db.lock().unwrap().clone() else { return Ok(files) };
+    let meta = db.open_tree("meta").map_err(|e| e.to_string())?;
+    Ok(meta.scan_prefix(project).values().filter_map(Result::ok)
+        .filter_map(|v| decode_from_slice::<FileNode, _>(&v, config::standard()).ok()).map(|r| r.0)
+        .filter(|n| !n.is_folder && Path::new(&n.path).starts_with(project)).map(|n| n.path).collect())
+}
+
+/// Recency boost per recently opened path.
+fn recent_boosts(state: &AppState, project: &str) -> Result<HashMap<String, i32>, String> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut st = conn.prepare("SELECT path, opened_at, count FROM recent_files WHERE project_id = ?").map_err(|e| e.to_string())?;
+    let rows = st.query_map(params![project], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, i64>(2)?))).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(Result::ok).map(|(p, at, count)| {
+        let age_days = (now - at).max(0) as f64 / 86_400_000.0;
+        (p, (RECENT_BOOST * 0.5f64.powf(age_days)) as i32 + count.min(10) as i32)
+    }).collect())
+}
+
+fn relative(project: &str, path: &str) -> String {
+    path.strip_prefix(project).unwrap_or(path).trim_start_matches(['/', '\\']).to_string()
+}
+
+/// Ranks project files against `query`. An empty query lists recently opened files.
+#[tauri::command]
+pub async fn find_files(state: State<'_, AppState>, project: String, query: String, limit: Option<usize>) -> Result<Vec<FileMatch>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let (files, recent) = (project_files(&state, &project)?, recent_boosts(&state, &project)?);
+    tauri::async_runtime::spawn_blocking(move || {
+        let q: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut res: Vec<FileMatch> = if q.is_empty() {
+            files.into_iter().filter_map(|p| recent.get(&p).map(|&b| FileMatch { relative: relative(&project, &p), score: b, positions: Vec::new(), recent: true, path: p })).collect()
+        } else {
+            files.into_par_iter().filter_map(|p| {
+                let rel = relative(&project, &p);
+                let m = fuzzy_match(&q, &rel)?;
+                let boost = recent.get(&p).copied();
+                Some(FileMatch { score: m.score + boost.unwrap_or(0), positions: m.positions, recent: boost.is_some(), relative: rel, path: p })
+            }).collect()
+        };
+        res.sort_by(|a, b| b.score.cmp(&a.score).then(a.relative.len().cmp(&b.relative.len())));
+        res.truncate(limit);
+        Ok(res)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn mark_file_opened(state: State<'_, AppState>, project: String, path: String) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute("INSERT INTO recent_files (path, project_id, opened_at, count) VALUES (?, ?, ?, 1) ON CONFLICT(path) DO UPDATE SET project_id = excluded.project_id, opened_at = excluded.opened_at, count = count + 1",
+        params![path, project, chrono::Utc::now().timestamp_millis()]).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM recent_files WHERE project_id = ? AND path NOT IN (SELECT path FROM recent_files WHERE project_id = ? ORDER BY opened_at DESC LIMIT ?)",
+        params![project, project, RECENT_KEEP as i64]).map_err(|e| e.to_string())?;
+    Ok(())
+}
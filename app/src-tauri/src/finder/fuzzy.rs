@@ -0,0 +1,66 @@
+
+const SCORE_MATCH: i32 = 16;
+const GAP_START: i32 = -3;
+const GAP_EXTENSION: i32 = -1;
+const BONUS_SEPARATOR: i32 = 9;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_BASENAME: i32 = 2;
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+const NEG: i32 = i32::MIN / 2;
+
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Char indices of the matched characters in the text.
+    pub positions: Vec<usize>,
+}
+
+fn is_separator(c: char) -> bool { c == '/' || c == '\\' }
+
+fn char_bonus(prev: char, c: char) -> i32 {
+    if is_separator(prev) { BONUS_SEPARATOR }
+    else if matches!(prev, '_' | '-' | '.' | ' ') { BONUS_BOUNDARY }
+    else if (prev.is_lowercase() && c.is_uppercase()) || (!prev.is_ascii_digit() && c.is_ascii_digit()) { BONUS_CAMEL }
+    else { 0 }
+}
+
+/// fzf-style scoring: the best alignment of `query` as a subsequence of `text`, rewarding matches at
+/// word boundaries, in the file name and in runs, and penalizing gaps. Smart case: case-sensitive only
+/// when the query has an uppercase letter.
+pub fn fuzzy_match(query: &[char], text: &str) -> Option<FuzzyMatch> {
+    let t: Vec<char> = text.chars().collect();
+    let (n, m) = (query.len(), t.len());
+    if n == 0 { return Some(FuzzyMatch { score: 0, positions: Vec::new() }); }
+    let sensitive = query.iter().any(|c| c.is_uppercase());
+    let eq = |a: char, b: char| if sensitive { a == b } else { a.to_lowercase().eq(b.to_lowercase()) };
+    let mut qi = 0;
+    for &c in &t { if qi < n && eq(query[qi], c) { qi += 1; } }
+    if qi < n { return None; }
+
+    let base = t.iter().rposition(|&c| is_separator(c)).map_or(0, |i| i + 1);
+    let bonus: Vec<i32> = (0..m).map(|j| if j == 0 { BONUS_SEPARATOR } else { char_bonus(t[j - 1], t[j]) } + if j >= base { BONUS_BASENAME } else { 0 }).collect();
+    let (mut h, mut from) = (vec![NEG; n * m], vec![usize::MAX; n * m]);
+    for i in 0..n {
+        // Best predecessor ending two or more chars back, with the gap already charged.
+        let (mut skip, mut skip_from) = (NEG, usize::MAX);
+        for j in 0..m {
+            if i > 0 && j >= 2 {
+                let opened = h[(i - 1) * m + j - 2] + GAP_START;
+                if opened > skip + GAP_EXTENSION { skip = opened; skip_from = j - 2; } else { skip += GAP_EXTENSION; }
+            }
+            if !eq(query[i], t[j]) { continue; }
+            if i == 0 { h[j] = SCORE_MATCH + bonus[j] * FIRST_CHAR_MULTIPLIER; continue; }
+            let consec = if j >= 1 { h[(i - 1) * m + j - 1] + BONUS_CONSECUTIVE } else { NEG };
+            let (best, k) = if consec >= skip { (consec, j.wrapping_sub(1)) } else { (skip, skip_from) };
+            if best <= NEG / 2 { continue; }
+            h[i * m + j] = best + SCORE_MATCH + bonus[j];
+            from[i * m + j] = k;
+        }
+    }
+    let (mut j, score) = (0..m).map(|j| (j, h[(n - 1) * m + j])).max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+    if score <= NEG / 2 { return None; }
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() { positions[i] = j; j = from[i * m + j]; }
+    Some(FuzzyMatch { score, positions })
+}
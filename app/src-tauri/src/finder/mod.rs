@@ -0,0 +1,5 @@
+
+pub mod fuzzy;
+pub mod commands;
+
+pub use commands::{find_files, mark_file_opened};
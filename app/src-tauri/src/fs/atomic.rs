@@ -0,0 +1,89 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+static SEQ: AtomicU64 = AtomicU64::new(0);
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+pub fn mtime_ms(meta: &fs::Metadata) -> i64 {
+    meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_millis() as i64).unwrap_or_default()
+}
+
+/// Gives the temp file the original's owner and extended attributes. Both are best effort:
+/// only root may hand a file to another user, and some attributes are read-only.
+#[cfg(unix)]
+fn copy_unix_attrs(from: &Path, to: &fs::File, meta: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::fchown(to, Some(meta.uid()), Some(meta.gid()));
+    if let Ok(names) = xattr::list(from) {
+        for name in names {
+            if let Ok(Some(value)) = xattr::get(from, &name) { let _ = xattr::FileExt::set_xattr(to, &name, &value); }
+        }
+    }
+}
+
+/// Replaces `path` via a synced temp file in the same directory and a rename, so readers never see a partial write.
+/// Symlinks are followed, and the mode, owner and extended attributes of the existing file are kept. A file with
+/// other hard links, or in a directory we cannot create the temp file in, is overwritten in place instead.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let path: PathBuf = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    #[cfg(unix)]
+    if fs::metadata(&path).is_ok_and(|m| std::os::unix::fs::MetadataExt::nlink(&m) > 1) { return write_in_place(&path, bytes); }
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dir.join(format!(".{}.oxide-tmp-{}-{}", name, std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed)));
+    let mut f = match fs::File::create(&tmp) {
+        Ok(f) => f,
+        Err(_) if path.is_file() => return write_in_place(&path, bytes),
+        Err(e) => return Err(e),
+    };
+    let res = (|| {
+        f.write_all(bytes)?;
+        if let Ok(meta) = fs::metadata(&path) {
+            f.set_permissions(meta.permissions())?;
+            #[cfg(unix)]
+            copy_unix_attrs(&path, &f, &meta);
+        }
+        f.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        // persist the rename itself; best effort, since the new content is already in place
+        #[cfg(unix)]
+        let _ = fs::File::open(dir).and_then(|d| d.sync_all());
+        Ok(())
+    })();
+    if res.is_err() { let _ = fs::remove_file(&tmp); }
+    res
+}
+
+/// Overwrites `path` in place after saving its current content to a synced, private snapshot in the
+/// temp dir. A failed overwrite is undone from the snapshot; if even that fails the snapshot is kept
+/// and named in the error.
+fn write_in_place(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let original = fs::read(path)?;
+    let snapshot = std::env::temp_dir().join(format!("oxide-inplace-{}-{}", std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed)));
+    {
+        let mut opts = fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+        let mut s = opts.open(&snapshot)?;
+        s.write_all(&original).and_then(|_| s.sync_all()).inspect_err(|_| { let _ = fs::remove_file(&snapshot); })?;
+    }
+    let overwrite = |data: &[u8]| {
+        let mut f = fs::OpenOptions::new().write(true).open(path)?;
+        f.write_all(data)?;
+        f.set_len(data.len() as u64)?;
+        f.sync_all()
+    };
+    match overwrite(bytes) {
+        Ok(()) => { let _ = fs::remove_file(&snapshot); Ok(()) }
+        Err(e) if overwrite(&original).is_ok() => { let _ = fs::remove_file(&snapshot); Err(e) }
+        Err(e) => Err(std::io::Error::new(e.kind(), format!("{}; the original content is saved at {}", e, snapshot.display()))),
+    }
+}
@@ -0,0 +1,191 @@
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// Bytes inspected for NUL when deciding whether a file is binary, as git and ripgrep do.
+const BINARY_SNIFF: usize = 8192;
+
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+    /// Some bytes were invalid in `encoding` and decoded as U+FFFD.
+    pub malformed: bool,
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF)].contains(&0)
+}
+
+/// UTF-16 without a BOM, recognized by the NUL high bytes of mostly-ASCII text.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let units = bytes[..bytes.len().min(BINARY_SNIFF)].chunks_exact(2);
+    let n = units.len();
+    if n < 2 { return None; }
+    let (even, odd) = units.fold((0, 0), |(e, o), u| (e + (u[0] == 0) as usize, o + (u[1] == 0) as usize));
+    let enc = match (even, odd) {
+        (0, o) if o * 10 >= n * 9 => UTF_16LE,
+        (e, 0) if e * 10 >= n * 9 => UTF_16BE,
+        _ => return None,
+    };
+    let text = enc.decode_without_bom_handling_and_without_replacement(bytes)?;
+    (!text.contains('\0')).then_some(enc)
+}
+
+/// Decodes text from a BOM (UTF-8, UTF-16 LE/BE), as BOM-less UTF-16, as UTF-8, or failing that as
+/// Windows-1252 (a Latin-1 superset). Returns `None` for binary content.
+pub fn decode_text(bytes: &[u8]) -> Option<Decoded> {
+    if let Some((enc, _)) = Encoding::for_bom(bytes) { return Some(decode_with(bytes, enc)); }
+    if let Some(enc) = sniff_utf16(bytes) { return Some(decode_with(bytes, enc)); }
+    if is_binary(bytes) { return None; }
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Some(Decoded { text: s.to_string(), encoding: UTF_8, bom: false, malformed: false }),
+        Err(_) => Some(decode_with(bytes, WINDOWS_1252)),
+    }
+}
+
+/// Decodes as `encoding` regardless of content, dropping a BOM only if it belongs to that encoding.
+pub fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> Decoded {
+    let bom = Encoding::for_bom(bytes).filter(|(e, _)| *e == encoding).map(|(_, len)| len);
+    let (text, malformed) = encoding.decode_without_bom_handling(&bytes[bom.unwrap_or(0)..]);
+    Decoded { text: text.into_owned(), encoding, bom: bom.is_some(), malformed }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding { Lf, Crlf, Cr }
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self { LineEnding::Lf => "\n", LineEnding::Crlf => "\r\n", LineEnding::Cr => "\r" }
+    }
+}
+
+/// The most common line break in `text` (`None` without any) and whether other kinds occur too.
+pub fn line_endings(text: &str) -> (Option<LineEnding>, bool) {
+    let (b, mut counts) = (text.as_bytes(), [0usize; 3]);
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'\r' if b.get(i + 1) == Some(&b'\n') => { counts[1] += 1; i += 1; }
+            b'\r' => counts[2] += 1,
+            b'\n' => counts[0] += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    let styles = [LineEnding::Lf, LineEnding::Crlf, LineEnding::Cr];
+    let top = (0..3).filter(|&k| counts[k] > 0).max_by_key(|&k| counts[k]).map(|k| styles[k]);
+    (top, counts.iter().filter(|&&c| c > 0).count() > 1)
+}
+
+/// The line ending every break in `text` uses, or `None` when there are no breaks or they are mixed.
+pub fn detect_line_ending(text: &str) -> Option<LineEnding> {
+    match line_endings(text) { (le, false) => le, _ => None }
+}
+
+/// Rewrites every line break in `text` as `le`.
+pub fn convert_line_endings(text: &str, le: LineEnding) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(['\r', '\n']) {
+        out.push_str(&rest[..i]);
+        out.push_str(le.as_str());
+        rest = &rest[i + if rest[i..].starts_with("\r\n") { 2 } else { 1 }..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Encodes `text` for saving. Fails rather than substituting characters the encoding cannot represent.
+pub fn encode_text(text: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>, String> {
+    let utf16 = |be: bool| {
+        let mut out = if bom { if be { vec![0xFE, 0xFF] } else { vec![0xFF, 0xFE] } } else { Vec::new() };
+        for u in text.encode_utf16() { out.extend(if be { u.to_be_bytes() } else { u.to_le_bytes() }); }
+        out
+    };
+    if encoding == UTF_16LE { return Ok(utf16(false)); }
+    if encoding == UTF_16BE { return Ok(utf16(true)); }
+    if encoding == UTF_8 {
+        let mut out = if bom { vec![0xEF, 0xBB, 0xBF] } else { Vec::new() };
+        out.extend_from_slice(text.as_bytes());
+        return Ok(out);
+    }
+    let (bytes, _, unmappable) = encoding.encode(text);
+    if unmappable { return Err(format!("The text contains characters that {} cannot represent", encoding.name())); }
+    Ok(bytes.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> { text.encode_utf16().flat_map(u16::to_le_bytes).collect() }
+
+    #[test]
+    fn decode_text_follows_the_bom() {
+        let d = decode_text(&[0xEF, 0xBB, 0xBF, b'h', b'i']).unwrap();
+        assert_eq!((d.text.as_str(), d.encoding, d.bom), ("hi", UTF_8, true));
+        let d = decode_text(&[0xFE, 0xFF, 0, b'h', 0, b'i']).unwrap();
+        assert_eq!((d.text.as_str(), d.encoding, d.bom), ("hi", UTF_16BE, true));
+    }
+
+    #[test]
+    fn decode_text_falls_back_from_utf8_to_windows_1252() {
+        let d = decode_text("héllo".as_bytes()).unwrap();
+        assert_eq!((d.text.as_str(), d.encoding, d.bom, d.malformed), ("héllo", UTF_8, false, false));
+        let d = decode_text(b"caf\xE9").unwrap();
+        assert_eq!((d.text.as_str(), d.encoding), ("café", WINDOWS_1252));
+    }
+
+    #[test]
+    fn decode_text_rejects_binary() {
+        assert!(decode_text(b"PK\x03\x04\0\0\x14\0").is_none());
+        assert!(decode_text(b"").is_some_and(|d| d.text.is_empty()));
+    }
+
+    #[test]
+    fn sniff_utf16_needs_mostly_ascii_text_without_nul() {
+        let bytes = utf16le("hello world");
+        assert_eq!(sniff_utf16(&bytes), Some(UTF_16LE));
+        let d = decode_text(&bytes).unwrap();
+        assert_eq!((d.text.as_str(), d.encoding, d.bom), ("hello world", UTF_16LE, false));
+        let be: Vec<u8> = "hello".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(sniff_utf16(&be), Some(UTF_16BE));
+        assert_eq!(sniff_utf16(&utf16le("h\0llo")), None);
+        assert_eq!(sniff_utf16(&[b'a', 0]), None);
+        assert_eq!(sniff_utf16(b"plain ascii"), None);
+    }
+
+    #[test]
+    fn line_endings_report_the_most_common_and_mixing() {
+        assert_eq!(line_endings("a\r\nb\r\nc\n"), (Some(LineEnding::Crlf), true));
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), None);
+        assert_eq!(detect_line_ending("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(line_endings("none"), (None, false));
+    }
+
+    #[test]
+    fn convert_line_endings_rewrites_every_kind_of_break() {
+        let text = "a\r\nb\nc\rd";
+        assert_eq!(convert_line_endings(text, LineEnding::Lf), "a\nb\nc\nd");
+        assert_eq!(convert_line_endings(text, LineEnding::Crlf), "a\r\nb\r\nc\r\nd");
+        assert_eq!(convert_line_endings(text, LineEnding::Cr), "a\rb\rc\rd");
+        assert_eq!(convert_line_endings("\n\n", LineEnding::Crlf), "\r\n\r\n");
+        assert_eq!(convert_line_endings("none", LineEnding::Crlf), "none");
+    }
+
+    #[test]
+    fn encode_text_writes_the_bom_of_unicode_encodings() {
+        assert_eq!(encode_text("hé", UTF_8, false).unwrap(), "hé".as_bytes());
+        assert_eq!(encode_text("hé", UTF_8, true).unwrap(), [&[0xEF, 0xBB, 0xBF][..], "hé".as_bytes()].concat());
+        assert_eq!(encode_text("hi", UTF_16LE, true).unwrap(), [0xFF, 0xFE, b'h', 0, b'i', 0]);
+        assert_eq!(encode_text("hi", UTF_16BE, false).unwrap(), [0, b'h', 0, b'i']);
+    }
+
+    #[test]
+    fn encode_text_refuses_unmappable_characters() {
+        assert_eq!(encode_text("café", WINDOWS_1252, false).unwrap(), b"caf\xE9");
+        assert!(encode_text("日本", WINDOWS_1252, false).is_err());
+    }
+}
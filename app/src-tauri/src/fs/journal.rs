@@ -0,0 +1,247 @@
+
+use crate::fs::trash::{move_path, restore, trash};
+use crate::fs::{content_hash, write_atomic};
+use crate::state::AppState;
+use rusqlite::{params, Connection, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+const JOURNAL_LIMIT: i64 = 1000;
+
+/// File contents saved under the app data dir so a write can be reversed after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub file: PathBuf,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FsOp {
+    Rename { from: String, to: String },
+    /// `trash_id` is updated each time a redo trashes the entry again.
+    Delete { path: String, trash_id: String },
+    /// `before` is `None` when the write created the file.
+    Write { path: String, before: Option<Snapshot>, after: Snapshot },
+    /// `created` is the outermost directory that did not exist yet.
+    CreateDir { path: String, created: String },
+    /// `trash_id` is set while the copy is undone and sits in the trash.
+    Copy { from: String, to: String, trash_id: Option<String> },
+    /// Steps of one transfer, undone back to front as a unit.
+    Group { ops: Vec<FsOp> },
+}
+
+#[derive(Serialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    #[serde(flatten)]
+    pub op: FsOp,
+    pub created_at: i64,
+    pub undone: bool,
+    /// Set once undoing or redoing the entry failed; it is skipped from then on.
+    pub stale: bool,
+}
+
+impl Snapshot {
+    /// Stores `bytes` under their content hash, so identical contents share one file.
+    pub fn take(data_dir: &Path, bytes: &[u8]) -> Result<Self, String> {
+        let dir = data_dir.join("journal");
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let hash = content_hash(bytes);
+        let file = dir.join(&hash);
+        if !file.exists() { write_atomic(&file, bytes).map_err(|e| e.to_string())?; }
+        Ok(Snapshot { file, hash })
+    }
+}
+
+fn current_hash(path: &str) -> Option<String> { fs::read(path).ok().map(|b| content_hash(&b)) }
+
+fn exists(path: &str) -> bool { Path::new(path).symlink_metadata().is_ok() }
+
+/// Whether `target` exists as an entry other than `source`. A case-only rename on a
+/// case-insensitive filesystem finds the source itself at the target path.
+pub(crate) fn occupied(target: &Path, source: &Path) -> bool {
+    let Ok(t) = target.symlink_metadata() else { return false };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        !source.symlink_metadata().is_ok_and(|s| (s.dev(), s.ino()) == (t.dev(), t.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = t;
+        !matches!((fs::canonicalize(target), fs::canonicalize(source)), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+/// Directories from `path` up to `created`, innermost first, provided they hold nothing but that chain.
+fn empty_chain<'a>(path: &'a str, created: &str) -> Result<Vec<&'a Path>, String> {
+    let mut chain = Vec::new();
+    let mut p = Path::new(path);
+    loop {
+        let count = fs::read_dir(p).map_err(|e| format!("{}: {}", p.display(), e))?.count();
+        if count > chain.len().min(1) { return Err(format!("{} is not empty", p.display())); }
+        chain.push(p);
+        if p == Path::new(created) { return Ok(chain); }
+        p = p.parent().ok_or("Invalid directory chain")?;
+    }
+}
+
+impl FsOp {
+    fn snapshots(&self) -> Vec<&Path> {
+        match self {
+            FsOp::Write { before, after, .. } => before.iter().chain([after]).map(|s| s.file.as_path()).collect(),
+            FsOp::Group { ops } => ops.iter().flat_map(FsOp::snapshots).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Reverses the operation, refusing when the disk no longer looks like it did right after it.
+    fn undo(&mut self, data_dir: &Path, db: &Mutex<Connection>) -> Result<(), String> {
+        match self {
+            FsOp::Rename { from, to } => {
+                if !exists(to) || occupied(Path::new(from), Path::new(to)) { return Err(format!("Cannot rename {} back to {}", to, from)); }
+                // a merged move removes source directories once their entries are moved out
+                if let Some(parent) = Path::new(from.as_str()).parent() { fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+                move_path(Path::new(to), Path::new(from)).map_err(|e| e.to_string())
+            }
+            FsOp::Delete { trash_id, .. } => restore(trash_id, db).map(|_| ()),
+            FsOp::Write { path, before, after } => {
+                if current_hash(path).as_ref() != Some(&after.hash) { return Err(format!("{} changed since it was written", path)); }
+                match before {
+                    Some(s) => write_atomic(Path::new(path), &fs::read(&s.file).map_err(|e| e.to_string())?).map_err(|e| e.to_string()),
+                    None => fs::remove_file(path.as_str()).map_err(|e| e.to_string()),
+                }
+            }
+            FsOp::CreateDir { path, created } => {
+                for dir in empty_chain(path, created)? { fs::remove_dir(dir).map_err(|e| e.to_string())?; }
+                Ok(())
+            }
+            FsOp::Copy { to, trash_id, .. } => {
+                if !exists(to) { return Err(format!("{} no longer exists", to)); }
+                *trash_id = Some(trash(Path::new(to), data_dir, db)?.id);
+                Ok(())
+            }
+            FsOp::Group { ops } => {
+                for i in (0..ops.len()).rev() {
+                    if let Err(e) = ops[i].undo(data_dir, db) {
+                        // put back what was already undone, so the group stays all or nothing
+                        for op in &mut ops[i + 1..] { let _ = op.redo(data_dir, db); }
+                        return Err(e);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn redo(&mut self, data_dir: &Path, db: &Mutex<Connection>) -> Result<(), String> {
+        match self {
+            FsOp::Rename { from, to } => {
+                if !exists(from) || occupied(Path::new(to), Path::new(from)) { return Err(format!("Cannot rename {} to {} again", from, to)); }
+                move_path(Path::new(from), Path::new(to)).map_err(|e| e.to_string())
+            }
+            FsOp::Delete { path, trash_id } => { *trash_id = trash(Path::new(path), data_dir, db)?.id; Ok(()) }
+            FsOp::Write { path, before, after } => {
+                if current_hash(path) != before.as_ref().map(|s| s.hash.clone()) { return Err(format!("{} changed since the undo", path)); }
+                write_atomic(Path::new(path), &fs::read(&after.file).map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+            }
+            FsOp::CreateDir { path, .. } => fs::create_dir_all(path.as_str()).map_err(|e| e.to_string()),
+            FsOp::Copy { to, trash_id, .. } => {
+                let id = trash_id.take().ok_or_else(|| format!("{} is not in the trash", to))?;
+                restore(&id, db).map(|_| ()).inspect_err(|_| *trash_id = Some(id))
+            }
+            FsOp::Group { ops } => {
+                for i in 0..ops.len() {
+                    if let Err(e) = ops[i].redo(data_dir, db) {
+                        for op in ops[..i].iter_mut().rev() { let _ = op.undo(data_dir, db); }
+                        return Err(e);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Drops journal rows, and each snapshot file once no remaining row refers to it.
+fn forget(conn: &Connection, rows: Vec<(i64, String)>) {
+    for (id, data) in rows {
+        let _ = conn.execute("DELETE FROM fs_journal WHERE id = ?", params![id]);
+        let Ok(op) = serde_json::from_str::<FsOp>(&data) else { continue };
+        for f in op.snapshots() {
+            let Some(name) = f.file_name().and_then(|n| n.to_str()) else { continue };
+            let used = conn.query_row("SELECT EXISTS (SELECT 1 FROM fs_journal WHERE instr(op, ?) > 0)", params![name], |r| r.get::<_, bool>(0)).unwrap_or(true);
+            if !used { let _ = fs::remove_file(f); }
+        }
+    }
+}
+
+fn select(conn: &Connection, sql: &str, p: impl Params) -> Vec<(i64, String)> {
+    let Ok(mut st) = conn.prepare(sql) else { return vec![] };
+    st.query_map(p, |r| Ok((r.get(0)?, r.get(1)?))).map(|rows| rows.filter_map(Result::ok).collect()).unwrap_or_default()
+}
+
+/// Appends a completed operation. Anything undone before it can no longer be redone.
+pub fn record(db: &Mutex<Connection>, op: FsOp) {
+    let conn = db.lock().unwrap();
+    let redoable = select(&conn, "SELECT id, op FROM fs_journal WHERE undone = 1", []);
+    let Ok(data) = serde_json::to_string(&op) else { return };
+    if let Err(e) = conn.execute("INSERT INTO fs_journal (op, created_at, undone) VALUES (?, ?, 0)", params![data, chrono::Utc::now().timestamp_millis()]) {
+        eprintln!("[WARNING] Could not record file operation: {}", e);
+    }
+    // after the insert, so snapshots shared with the new entry are kept
+    forget(&conn, redoable);
+    forget(&conn, select(&conn, "SELECT id, op FROM fs_journal ORDER BY id DESC LIMIT -1 OFFSET ?", params![JOURNAL_LIMIT]));
+}
+
+/// Runs `step` on the next entry for undo (`undone = 0`, newest first) or redo (`undone = 1`, oldest first).
+/// An entry that fails is marked stale so it no longer blocks the ones behind it.
+fn step(db: &Mutex<Connection>, data_dir: &Path, undo: bool) -> Result<Option<FsOp>, String> {
+    static RUNNING: Mutex<()> = Mutex::new(());
+    let _running = RUNNING.lock().unwrap();
+    let sql = if undo { "SELECT id, op FROM fs_journal WHERE undone = 0 AND stale = 0 ORDER BY id DESC LIMIT 1" } else { "SELECT id, op FROM fs_journal WHERE undone = 1 AND stale = 0 ORDER BY id ASC LIMIT 1" };
+    let row = db.lock().map_err(|e| e.to_string())?.query_row(sql, [], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))).optional().map_err(|e| e.to_string())?;
+    let Some((id, data)) = row else { return Ok(None) };
+    let res = (|| {
+        let mut op: FsOp = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+        if undo { op.undo(data_dir, db)? } else { op.redo(data_dir, db)? }
+        Ok::<_, String>((serde_json::to_string(&op).map_err(|e| e.to_string())?, op))
+    })();
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    match res {
+        Ok((data, op)) => {
+            conn.execute("UPDATE fs_journal SET op = ?, undone = ? WHERE id = ?", params![data, undo, id]).map_err(|e| e.to_string())?;
+            Ok(Some(op))
+        }
+        Err(e) => {
+            let _ = conn.execute("UPDATE fs_journal SET stale = 1 WHERE id = ?", params![id]);
+            Err(e)
+        }
+    }
+}
+
+/// Reverses the most recent file operation; resolves with it, or `None` when there is nothing to undo.
+#[tauri::command]
+pub async fn undo_fs_op(state: State<'_, AppState>) -> Result<Option<FsOp>, String> {
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || step(&db, &data_dir, true)).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn redo_fs_op(state: State<'_, AppState>) -> Result<Option<FsOp>, String> {
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || step(&db, &data_dir, false)).await.map_err(|e| e.to_string())?
+}
+
+/// Newest entries first, including undone ones that are still redoable.
+#[tauri::command]
+pub fn list_fs_ops(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<JournalEntry>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut st = conn.prepare("SELECT id, op, created_at, undone, stale FROM fs_journal ORDER BY id DESC LIMIT ?").map_err(|e| e.to_string())?;
+    let rows = st.query_map(params![limit.unwrap_or(JOURNAL_LIMIT)], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?, r.get::<_, bool>(3)?, r.get::<_, bool>(4)?))).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(Result::ok).filter_map(|(id, op, created_at, undone, stale)| Some(JournalEntry { id, op: serde_json::from_str(&op).ok()?, created_at, undone, stale })).collect())
+}
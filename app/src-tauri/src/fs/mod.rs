@@ -2,14 +2,30 @@
 pub mod models;
 pub mod operations;
 pub mod scanning;
+pub mod atomic;
+pub mod encoding;
+pub mod trash;
+pub mod journal;
+pub mod transfer;
 
 pub use models::{FileNode, FilePatch, ScanResult};
 pub use operations::{read_text_file, write_text_file, rename_entry, delete_entry, log_to_file};
 pub use scanning::{scan_project, scan_project_streamed, index_images};
+pub use atomic::{content_hash, mtime_ms, write_atomic};
+pub use trash::{list_trash, restore_entry, empty_trash, TrashEntry};
+pub use journal::{undo_fs_op, redo_fs_op, list_fs_ops};
+pub use transfer::{copy_entries, move_entries, duplicate_entry, cancel_transfer, resolve_transfer_conflict};
+
+use crate::fs::journal::{record, FsOp};
+use crate::state::AppState;
+use std::path::Path;
 
 #[tauri::command]
-pub async fn create_dir(path: String) -> Result<(), String> {
-    std::fs::create_dir_all(path).map_err(|e| e.to_string())
+pub async fn create_dir(state: tauri::State<'_, AppState>, path: String) -> Result<(), String> {
+    let created = Path::new(&path).ancestors().take_while(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_err()).last().map(|p| p.to_string_lossy().to_string());
+    std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+    if let Some(created) = created { record(&state.db, FsOp::CreateDir { path, created }); }
+    Ok(())
 }
 
 #[tauri::command]
@@ -1,4 +1,5 @@
 
+use crate::fs::encoding::LineEnding;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
@@ -24,3 +25,43 @@ pub struct ScanResult {
     pub tree: Vec<FileNode>,
     pub images: Vec<FileNode>,
 }
+
+/// A text file decoded for the editor, with what `write_text_file` needs to save it back the same way.
+#[derive(Debug, Serialize)]
+pub struct TextDocument {
+    pub text: String,
+    /// WHATWG encoding name: `UTF-8`, `UTF-16LE`, `UTF-16BE`, `windows-1252`...
+    pub encoding: String,
+    pub bom: bool,
+    /// The most common line break; `None` when the file has none.
+    pub line_ending: Option<LineEnding>,
+    pub mixed_line_endings: bool,
+    /// Looks binary; `text` is then a lossy UTF-8 rendering unless an encoding was forced.
+    pub binary: bool,
+    /// Some bytes were invalid in `encoding` and show up as U+FFFD.
+    pub malformed: bool,
+    pub mtime: i64,
+    pub hash: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WriteOptions {
+    /// The save is refused when the file on disk no longer has this mtime or content hash.
+    pub expected_mtime: Option<i64>,
+    pub expected_hash: Option<String>,
+    /// Overrides for what is otherwise kept from the existing file. A document read with a forced
+    /// encoding must pass that encoding here, as the save otherwise re-detects it from disk.
+    pub encoding: Option<String>,
+    pub bom: Option<bool>,
+    pub line_ending: Option<LineEnding>,
+}
+
+/// State of the file after a save, to use as the next save's precondition.
+#[derive(Debug, Serialize)]
+pub struct SavedFile {
+    pub mtime: i64,
+    pub hash: String,
+    pub encoding: String,
+    pub bom: bool,
+    pub line_ending: Option<LineEnding>,
+}
@@ -1,29 +1,97 @@
 
+use crate::fs::encoding::{convert_line_endings, decode_text, decode_with, detect_line_ending, encode_text, line_endings};
+use crate::fs::journal::{occupied, record, FsOp, Snapshot};
+use crate::fs::models::{SavedFile, TextDocument, WriteOptions};
+use crate::fs::trash::{move_path, trash, TrashEntry};
+use crate::fs::{content_hash, mtime_ms, write_atomic};
+use crate::state::AppState;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
 use std::fs;
 use std::io::Write;
+use std::path::Path;
 
+/// Reads and decodes a file, detecting its encoding unless `encoding` (a label such as `utf-16be`
+/// or `latin1`) forces one, e.g. to re-open a file that was guessed wrong. Saving such a document,
+/// or one flagged `binary`, needs the same encoding passed back in `WriteOptions`.
 #[tauri::command]
-pub async fn read_text_file(path: String) -> Result<String, String> {
-    fs::read_to_string(path).map_err(|e| e.to_string())
+pub async fn read_text_file(path: String, encoding: Option<String>) -> Result<TextDocument, String> {
+    let forced = encoding.map(|l| Encoding::for_label(l.as_bytes()).ok_or_else(|| format!("Unknown encoding: {}", l))).transpose()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        // metadata first: a write landing in between makes the mtime stale, never the content
+        let meta = fs::metadata(&path).map_err(|e| e.to_string())?;
+        let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+        let detected = decode_text(&bytes);
+        let binary = detected.is_none();
+        let doc = match (forced, detected) {
+            (Some(enc), _) => decode_with(&bytes, enc),
+            (None, Some(doc)) => doc,
+            (None, None) => decode_with(&bytes, UTF_8),
+        };
+        let (line_ending, mixed_line_endings) = line_endings(&doc.text);
+        Ok(TextDocument {
+            line_ending, mixed_line_endings, binary, malformed: doc.malformed, encoding: doc.encoding.name().to_string(), bom: doc.bom,
+            mtime: mtime_ms(&meta), hash: content_hash(&bytes), text: doc.text,
+        })
+    }).await.map_err(|e| e.to_string())?
 }
 
+/// Saves atomically in the file's existing encoding, BOM and line endings unless `options` override them.
+/// With `expected_mtime` or `expected_hash` the save fails if the file changed on disk since it was read.
+/// A binary file is only overwritten when `encoding` is given explicitly.
 #[tauri::command]
-pub async fn write_text_file(path: String, content: String) -> Result<(), String> {
-    fs::write(path, content).map_err(|e| e.to_string())
+pub async fn write_text_file(state: tauri::State<'_, AppState>, path: String, content: String, options: Option<WriteOptions>) -> Result<SavedFile, String> {
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let o = options.unwrap_or_default();
+        let existing = fs::read(&path).ok();
+        if o.expected_mtime.is_some() || o.expected_hash.is_some() {
+            let meta = fs::metadata(&path).map_err(|_| format!("{} was deleted on disk", path))?;
+            let hash = existing.as_deref().map(content_hash);
+            if o.expected_mtime.is_some_and(|m| m != mtime_ms(&meta)) || o.expected_hash.is_some_and(|h| Some(h) != hash) {
+                return Err(format!("{} was modified on disk", path));
+            }
+        }
+        let forced = o.encoding.as_ref().map(|l| Encoding::for_label(l.as_bytes()).ok_or_else(|| format!("Unknown encoding: {}", l))).transpose()?;
+        let current = match (existing.as_deref(), forced) {
+            (Some(bytes), Some(enc)) => Some(decode_with(bytes, enc)),
+            (Some(bytes), None) => Some(decode_text(bytes).ok_or_else(|| format!("{} is a binary file; pass an explicit encoding to overwrite it", path))?),
+            (None, _) => None,
+        };
+        let encoding = forced.or(current.as_ref().map(|d| d.encoding)).unwrap_or(UTF_8);
+        let bom = o.bom.unwrap_or(current.as_ref().is_some_and(|d| d.bom)) && [UTF_8, UTF_16LE, UTF_16BE].contains(&encoding);
+        let text = match o.line_ending.or_else(|| detect_line_ending(&current?.text)) {
+            Some(le) => convert_line_endings(&content, le),
+            None => content,
+        };
+        let bytes = encode_text(&text, encoding, bom)?;
+        let before = existing.map(|b| Snapshot::take(&data_dir, &b)).transpose()?;
+        write_atomic(Path::new(&path), &bytes).map_err(|e| e.to_string())?;
+        let mtime = fs::metadata(&path).map(|m| mtime_ms(&m)).unwrap_or_default();
+        record(&db, FsOp::Write { after: Snapshot::take(&data_dir, &bytes)?, path, before });
+        Ok(SavedFile { mtime, hash: content_hash(&bytes), encoding: encoding.name().to_string(), bom, line_ending: detect_line_ending(&text) })
+    }).await.map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-pub async fn rename_entry(path: String, new_name: String) -> Result<String, String> {
-    let old = std::path::Path::new(&path);
+pub async fn rename_entry(state: tauri::State<'_, AppState>, path: String, new_name: String) -> Result<String, String> {
+    let old = Path::new(&path);
     let new = old.parent().ok_or("Invalid path")?.join(new_name);
-    fs::rename(&path, &new).map_err(|e| e.to_string())?;
-    Ok(new.to_string_lossy().to_string())
+    if occupied(&new, old) { return Err(format!("{} already exists", new.display())); }
+    move_path(old, &new).map_err(|e| e.to_string())?;
+    let new = new.to_string_lossy().to_string();
+    record(&state.db, FsOp::Rename { from: path, to: new.clone() });
+    Ok(new)
 }
 
+/// Moves the entry to the trash rather than deleting it; `restore_entry` brings it back.
 #[tauri::command]
-pub async fn delete_entry(path: String) -> Result<(), String> {
-    let meta = fs::metadata(&path).map_err(|e| e.to_string())?;
-    if meta.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }.map_err(|e| e.to_string())
+pub async fn delete_entry(state: tauri::State<'_, AppState>, path: String) -> Result<TrashEntry, String> {
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry = trash(Path::new(&path), &data_dir, &db)?;
+        record(&db, FsOp::Delete { path, trash_id: entry.id.clone() });
+        Ok(entry)
+    }).await.map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
@@ -0,0 +1,346 @@
+
+use crate::fs::journal::{record, FsOp};
+use crate::fs::trash::{remove_tree, restore, trash};
+use crate::state::AppState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+const CHUNK: usize = 1024 * 1024;
+const TICK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    Skip,
+    /// Moves the existing entry to the trash once the new one is ready; a directory landing on a
+    /// directory is merged into it instead.
+    Overwrite,
+    /// Keeps both, naming the new entry `name (n).ext`.
+    Rename,
+    /// Emits `transfer-conflict-{id}` and waits for `resolve_transfer_conflict`.
+    Ask,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransferProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub skipped: u64,
+    pub current: Option<String>,
+    /// Where each source ended up, in order; skipped sources are left out.
+    pub targets: Vec<String>,
+    pub done: bool,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferConflict {
+    pub source: String,
+    pub target: String,
+    pub source_is_dir: bool,
+    pub target_is_dir: bool,
+}
+
+/// Cancel flag of a running transfer and the channel its conflict answers arrive on.
+pub struct TransferControl {
+    cancel: AtomicBool,
+    answers: Mutex<Sender<(ConflictPolicy, bool)>>,
+}
+
+enum Target { Skip, Write(PathBuf), Replace(PathBuf), Merge(PathBuf) }
+
+struct Transfer<'a> {
+    app: &'a AppHandle,
+    id: &'a str,
+    data_dir: &'a Path,
+    db: &'a Mutex<Connection>,
+    moving: bool,
+    policy: ConflictPolicy,
+    control: &'a TransferControl,
+    answers: Receiver<(ConflictPolicy, bool)>,
+    progress: TransferProgress,
+    last_emit: Instant,
+    buf: Vec<u8>,
+    /// Operations to journal for the whole transfer; a merge yields one per placed entry.
+    journal: Vec<FsOp>,
+}
+
+/// Files and bytes below `path`, not following symlinks.
+fn measure(path: &Path) -> (u64, u64) {
+    let Ok(meta) = fs::symlink_metadata(path) else { return (0, 0) };
+    if !meta.is_dir() { return (1, meta.len()); }
+    fs::read_dir(path).into_iter().flatten().filter_map(Result::ok)
+        .map(|e| measure(&e.path())).fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+}
+
+/// First `name (n).ext` next to `path` that does not exist yet.
+fn free_name(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..).map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext))).find(|p| p.symlink_metadata().is_err()).unwrap()
+}
+
+/// Hidden sibling of `path` that a replacement is assembled under before it takes its place.
+fn staging_path(path: &Path) -> PathBuf {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.oxide-tmp-{}-{}", name, std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed)))
+}
+
+fn lossy(p: &Path) -> String { p.to_string_lossy().to_string() }
+
+impl Transfer<'_> {
+    fn cancelled(&self) -> bool { self.control.cancel.load(Ordering::Relaxed) }
+
+    fn emit(&mut self, force: bool) {
+        if !force && self.last_emit.elapsed() < TICK { return; }
+        self.last_emit = Instant::now();
+        let _ = self.app.emit(&format!("transfer-progress-{}", self.id), &self.progress);
+    }
+
+    fn advance(&mut self, files: u64, bytes: u64) {
+        self.progress.files_done += files;
+        self.progress.bytes_done += bytes;
+        self.emit(false);
+    }
+
+    /// Waits for the user's answer; "apply to all" replaces the policy for the rest of the transfer.
+    fn ask(&mut self, conflict: TransferConflict) -> ConflictPolicy {
+        self.emit(true);
+        let _ = self.app.emit(&format!("transfer-conflict-{}", self.id), conflict);
+        loop {
+            if self.cancelled() { return ConflictPolicy::Skip; }
+            match self.answers.recv_timeout(TICK) {
+                Ok((p, all)) => {
+                    let p = if p == ConflictPolicy::Ask { ConflictPolicy::Skip } else { p };
+                    if all { self.policy = p; }
+                    return p;
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return ConflictPolicy::Skip,
+            }
+        }
+    }
+
+    fn resolve(&mut self, src: &Path, dst: &Path) -> io::Result<Target> {
+        if src == dst { return Ok(if self.moving { Target::Skip } else { Target::Write(free_name(dst)) }); }
+        let Ok(dm) = fs::symlink_metadata(dst) else { return Ok(Target::Write(dst.to_path_buf())) };
+        let sm = fs::symlink_metadata(src)?;
+        let policy = match self.policy {
+            ConflictPolicy::Ask => self.ask(TransferConflict {
+                source: src.to_string_lossy().to_string(), target: dst.to_string_lossy().to_string(), source_is_dir: sm.is_dir(), target_is_dir: dm.is_dir(),
+            }),
+            p => p,
+        };
+        Ok(match policy {
+            ConflictPolicy::Skip | ConflictPolicy::Ask => Target::Skip,
+            ConflictPolicy::Rename => Target::Write(free_name(dst)),
+            ConflictPolicy::Overwrite if sm.is_dir() && dm.is_dir() => Target::Merge(dst.to_path_buf()),
+            ConflictPolicy::Overwrite => Target::Replace(dst.to_path_buf()),
+        })
+    }
+
+    /// Copies or moves `src` to `dst`, resolving a conflict there first. Returns where it landed.
+    /// A replacement is assembled under a staging name, so the entry it replaces stays untouched
+    /// until the new one is complete.
+    fn place(&mut self, src: &Path, dst: &Path) -> io::Result<Option<PathBuf>> {
+        if self.cancelled() { return Ok(None); }
+        let (target, replace) = match self.resolve(src, dst)? {
+            Target::Skip if src == dst => { let (f, b) = measure(src); self.advance(f, b); return Ok(Some(dst.to_path_buf())); }
+            Target::Skip => { let (f, b) = measure(src); self.progress.skipped += f; self.advance(f, b); return Ok(None); }
+            Target::Merge(p) => { self.merge(src, &p)?; return Ok(Some(p)); }
+            Target::Write(p) => (p, false),
+            Target::Replace(p) => (p, true),
+        };
+        let staged = if replace { staging_path(&target) } else { target.clone() };
+        let renamed = self.moving && match fs::rename(src, &staged) {
+            Ok(()) => { let (f, b) = measure(&staged); self.advance(f, b); true }
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => false,
+            Err(e) => return Err(e),
+        };
+        if !renamed {
+            if let Err(e) = self.copy(src, &staged) { if replace { let _ = remove_tree(&staged); } return Err(e); }
+            // a cancelled move or replacement leaves the source and target whole and drops the partial copy
+            if self.cancelled() && (self.moving || replace) { remove_tree(&staged)?; return Ok(None); }
+            // a plain copy keeps what it got through of a directory, but a file cancelled midway is gone
+            if staged.symlink_metadata().is_err() { return Ok(None); }
+        }
+        if replace {
+            if let Err(e) = self.swap_in(&staged, &target) {
+                let _ = if renamed { fs::rename(&staged, src) } else { remove_tree(&staged) };
+                return Err(e);
+            }
+        }
+        if self.moving {
+            if !renamed { remove_tree(src)?; }
+            self.journal.push(FsOp::Rename { from: lossy(src), to: lossy(&target) });
+        } else {
+            self.journal.push(FsOp::Copy { from: lossy(src), to: lossy(&target), trash_id: None });
+        }
+        Ok(Some(target))
+    }
+
+    /// Trashes `target` and renames `staged` into its place, restoring `target` if that fails.
+    fn swap_in(&mut self, staged: &Path, target: &Path) -> io::Result<()> {
+        let entry = trash(target, self.data_dir, self.db).map_err(io::Error::other)?;
+        if let Err(e) = fs::rename(staged, target) {
+            let _ = restore(&entry.id, self.db);
+            return Err(e);
+        }
+        self.journal.push(FsOp::Delete { path: lossy(target), trash_id: entry.id });
+        Ok(())
+    }
+
+    fn merge(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        for e in fs::read_dir(src)? {
+            let e = e?;
+            self.place(&e.path(), &dst.join(e.file_name()))?;
+        }
+        if self.moving { let _ = fs::remove_dir(src); }
+        Ok(())
+    }
+
+    /// Copies into a path that does not exist; nothing below it can conflict.
+    fn copy(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        let meta = fs::symlink_metadata(src)?;
+        if meta.is_dir() {
+            fs::create_dir(dst)?;
+            for e in fs::read_dir(src)? {
+                if self.cancelled() { break; }
+                let e = e?;
+                self.copy(&e.path(), &dst.join(e.file_name()))?;
+            }
+            fs::set_permissions(dst, meta.permissions())
+        } else if meta.file_type().is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(src)?, dst)?;
+            #[cfg(not(unix))]
+            fs::copy(src, dst)?;
+            self.advance(1, meta.len());
+            Ok(())
+        } else {
+            self.progress.current = Some(src.to_string_lossy().to_string());
+            let res = self.copy_file(src, dst, &meta);
+            if res.is_err() || self.cancelled() { let _ = fs::remove_file(dst); }
+            res
+        }
+    }
+
+    fn copy_file(&mut self, src: &Path, dst: &Path, meta: &fs::Metadata) -> io::Result<()> {
+        let (mut r, mut w) = (File::open(src)?, File::create(dst)?);
+        let mut buf = std::mem::take(&mut self.buf);
+        let res = (|| loop {
+            if self.cancelled() { return Ok(()); }
+            let n = r.read(&mut buf)?;
+            if n == 0 { return fs::set_permissions(dst, meta.permissions()); }
+            w.write_all(&buf[..n])?;
+            self.progress.bytes_done += n as u64;
+            self.emit(false);
+        })();
+        self.buf = buf;
+        if res.is_ok() && !self.cancelled() { self.advance(1, 0); }
+        res
+    }
+}
+
+async fn run(app: AppHandle, state: State<'_, AppState>, id: String, sources: Vec<String>, dest_dir: String, policy: ConflictPolicy, moving: bool) -> Result<TransferProgress, String> {
+    let (tx, answers) = channel();
+    let control = Arc::new(TransferControl { cancel: AtomicBool::new(false), answers: Mutex::new(tx) });
+    {
+        let mut transfers = state.transfers.lock().unwrap();
+        if transfers.contains_key(&id) { return Err(format!("Transfer already running: {}", id)); }
+        transfers.insert(id.clone(), control.clone());
+    }
+    let (transfers, db, data_dir, id_c) = (state.transfers.clone(), state.db.clone(), state.data_dir.clone(), id.clone());
+    let res = tauri::async_runtime::spawn_blocking(move || {
+        let dest = std::path::absolute(&dest_dir).map_err(|e| e.to_string())?;
+        if !dest.is_dir() { return Err(format!("Not a directory: {}", dest_dir)); }
+        let sources = sources.iter().map(std::path::absolute).collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+        if let Some(s) = sources.iter().find(|s| dest.starts_with(s)) {
+            return Err(format!("Cannot {} {} into itself", if moving { "move" } else { "copy" }, s.display()));
+        }
+        if let Some(s) = sources.iter().find(|s| s.file_name().is_none()) { return Err(format!("Invalid source: {}", s.display())); }
+        let mut t = Transfer {
+            app: &app, id: &id, data_dir: &data_dir, db: &db, moving, policy, control: &control, answers,
+            progress: TransferProgress::default(), last_emit: Instant::now(), buf: vec![0; CHUNK], journal: Vec::new(),
+        };
+        for s in &sources {
+            let (f, b) = measure(s);
+            t.progress.files_total += f;
+            t.progress.bytes_total += b;
+        }
+        let mut failed = None;
+        for src in &sources {
+            if t.cancelled() { break; }
+            match t.place(src, &dest.join(src.file_name().unwrap_or_default())) {
+                Ok(Some(target)) => t.progress.targets.push(lossy(&target)),
+                Ok(None) => {}
+                Err(e) => { failed = Some(format!("{}: {}", src.display(), e)); break; }
+            }
+        }
+        // whatever was placed before a failure is still undoable
+        let mut ops = std::mem::take(&mut t.journal);
+        match ops.len() {
+            0 => {}
+            1 => record(&db, ops.remove(0)),
+            _ => record(&db, FsOp::Group { ops }),
+        }
+        if let Some(e) = failed { return Err(e); }
+        t.progress.current = None;
+        t.progress.done = true;
+        t.progress.cancelled = t.cancelled();
+        t.emit(true);
+        Ok(t.progress)
+    }).await.map_err(|e| e.to_string());
+    transfers.lock().unwrap().remove(&id_c);
+    res?
+}
+
+/// Copies files and directory trees into `dest_dir`, emitting `transfer-progress-{id}` as it goes.
+/// Resolves with the final progress once finished or cancelled. The transfer is journaled as one
+/// entry; undoing it trashes the copies and restores the entries they replaced.
+#[tauri::command]
+pub async fn copy_entries(app: AppHandle, state: State<'_, AppState>, id: String, sources: Vec<String>, dest_dir: String, policy: ConflictPolicy) -> Result<TransferProgress, String> {
+    run(app, state, id, sources, dest_dir, policy, false).await
+}
+
+/// Like `copy_entries` but renames where possible and copies then deletes across filesystems.
+/// Each moved entry, and each entry it replaced, is journaled with the rest of the transfer so
+/// `undo_fs_op` can put them back.
+#[tauri::command]
+pub async fn move_entries(app: AppHandle, state: State<'_, AppState>, id: String, sources: Vec<String>, dest_dir: String, policy: ConflictPolicy) -> Result<TransferProgress, String> {
+    run(app, state, id, sources, dest_dir, policy, true).await
+}
+
+/// Copies an entry next to itself as `name (n).ext` and returns the new path.
+#[tauri::command]
+pub async fn duplicate_entry(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<String, String> {
+    let parent = Path::new(&path).parent().ok_or("Invalid path")?.to_string_lossy().to_string();
+    let id = format!("duplicate-{:x}", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    let progress = run(app, state, id, vec![path], parent, ConflictPolicy::Rename, false).await?;
+    progress.targets.into_iter().next().ok_or_else(|| "Nothing was copied".to_string())
+}
+
+#[tauri::command]
+pub fn cancel_transfer(state: State<'_, AppState>, id: String) -> Result<bool, String> {
+    Ok(state.transfers.lock().unwrap().get(&id).map(|c| c.cancel.store(true, Ordering::Relaxed)).is_some())
+}
+
+/// Answers the pending `transfer-conflict-{id}` event; `apply_to_all` reuses the answer for later conflicts.
+#[tauri::command]
+pub fn resolve_transfer_conflict(state: State<'_, AppState>, id: String, policy: ConflictPolicy, apply_to_all: bool) -> Result<(), String> {
+    let transfers = state.transfers.lock().unwrap();
+    let control = transfers.get(&id).ok_or_else(|| format!("No transfer running: {}", id))?;
+    let sent = control.answers.lock().unwrap().send((policy, apply_to_all));
+    sent.map_err(|e| e.to_string())
+}
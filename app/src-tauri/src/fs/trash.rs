@@ -0,0 +1,309 @@
+
+use crate::state::AppState;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+const INFO_EXT: &str = ".trashinfo";
+
+#[derive(Debug, Serialize)]
+pub struct TrashEntry {
+    /// Location inside the trash; this is what `restore_entry` and `empty_trash` take.
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    /// Local time as stored in the `.trashinfo` file (`YYYY-MM-DDThh:mm:ss`).
+    pub deleted_at: String,
+    pub is_folder: bool,
+}
+
+/// A trash directory laid out as in the freedesktop Trash spec: `files/` holds the entries and
+/// `info/` one `.trashinfo` per entry with its original path and deletion date. The trash is shared
+/// with other apps, so only entries recorded in `trash_entries` are listed, restored or emptied.
+#[derive(Clone)]
+struct TrashDir {
+    root: PathBuf,
+    /// Mount point for `$topdir/.Trash-$uid` trashes, whose info paths are relative to it.
+    top: Option<PathBuf>,
+}
+
+/// `$XDG_DATA_HOME/Trash` on Linux, an Oxide-managed trash in the app data dir elsewhere.
+fn home_trash(data_dir: &Path) -> TrashDir {
+    #[cfg(target_os = "linux")]
+    {
+        let data_home = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).filter(|p| p.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")));
+        if let Some(d) = data_home { return TrashDir { root: d.join("Trash"), top: None }; }
+    }
+    TrashDir { root: data_dir.join("trash"), top: None }
+}
+
+#[cfg(target_os = "linux")]
+fn top_trash(top: &Path) -> TrashDir {
+    TrashDir { root: top.join(format!(".Trash-{}", unsafe { libc::getuid() })), top: Some(top.to_path_buf()) }
+}
+
+/// Topmost directory on the same device as `path`.
+#[cfg(target_os = "linux")]
+fn mount_top(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+    let dev = fs::symlink_metadata(path).ok()?.dev();
+    let mut top = path.parent()?;
+    while let Some(p) = top.parent() {
+        if fs::metadata(p).ok()?.dev() != dev { break; }
+        top = p;
+    }
+    Some(top.to_path_buf())
+}
+
+/// Percent-encodes a path for the `Path=` key, keeping `/` and RFC 2396 unreserved characters.
+fn encode(p: &Path) -> String {
+    p.as_os_str().as_encoded_bytes().iter().map(|&b| {
+        if b.is_ascii_alphanumeric() || b"/-_.~!*'()".contains(&b) { (b as char).to_string() } else { format!("%{:02X}", b) }
+    }).collect()
+}
+
+fn decode(s: &str) -> Option<PathBuf> {
+    let (b, mut out, mut i) = (s.as_bytes(), Vec::new(), 0);
+    while i < b.len() {
+        if b[i] == b'%' { out.push(u8::from_str_radix(s.get(i + 1..i + 3)?, 16).ok()?); i += 3; } else { out.push(b[i]); i += 1; }
+    }
+    #[cfg(unix)]
+    { Some(PathBuf::from(<OsString as std::os::unix::ffi::OsStringExt>::from_vec(out))) }
+    #[cfg(not(unix))]
+    { String::from_utf8(out).ok().map(PathBuf::from) }
+}
+
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for e in fs::read_dir(from)? { let e = e?; copy_tree(&e.path(), &to.join(e.file_name()))?; }
+        fs::set_permissions(to, meta.permissions())
+    } else if meta.file_type().is_symlink() {
+        #[cfg(unix)]
+        { std::os::unix::fs::symlink(fs::read_link(from)?, to) }
+        #[cfg(not(unix))]
+        { fs::copy(from, to).map(|_| ()) }
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+pub(crate) fn remove_tree(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }
+}
+
+/// Renames `from` to `to`, falling back to copy-then-delete across filesystems.
+pub(crate) fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_tree(from, to) { let _ = remove_tree(to); return Err(e); }
+            remove_tree(from)
+        }
+        r => r,
+    }
+}
+
+impl TrashDir {
+    fn files(&self) -> PathBuf { self.root.join("files") }
+
+    fn info(&self) -> PathBuf { self.root.join("info") }
+
+    fn info_path(&self, name: &str) -> PathBuf { self.info().join(format!("{}{}", name, INFO_EXT)) }
+
+    fn create(&self) -> io::Result<()> {
+        let mut b = fs::DirBuilder::new();
+        b.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut b, 0o700);
+        b.create(self.files())?;
+        b.create(self.info())
+    }
+
+    /// Moves `path` in under a free name, reserving it by creating the info file first.
+    /// Without `copy` a cross-device move fails instead of copying.
+    fn put(&self, path: &Path, copy: bool) -> io::Result<TrashEntry> {
+        self.create()?;
+        let base = path.file_name().ok_or(ErrorKind::InvalidInput)?.to_string_lossy().to_string();
+        let stored = self.top.as_ref().and_then(|t| path.strip_prefix(t).ok()).unwrap_or(path);
+        let deleted_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let is_folder = fs::symlink_metadata(path)?.is_dir();
+        let mut n = 1;
+        loop {
+            let name = if n == 1 { base.clone() } else { format!("{}.{}", base, n) };
+            n += 1;
+            let (info, target) = (self.info_path(&name), self.files().join(&name));
+            let mut f = match OpenOptions::new().write(true).create_new(true).open(&info) {
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                r => r?,
+            };
+            if target.symlink_metadata().is_ok() { let _ = fs::remove_file(&info); continue; }
+            let moved = write!(f, "[Trash Info]\nPath={}\nDeletionDate={}\n", encode(stored), deleted_at)
+                .and_then(|_| if copy { move_path(path, &target) } else { fs::rename(path, &target) });
+            if let Err(e) = moved { let _ = fs::remove_file(&info); return Err(e); }
+            return Ok(TrashEntry { id: target.to_string_lossy().to_string(), name, original_path: path.to_string_lossy().to_string(), deleted_at, is_folder });
+        }
+    }
+
+    fn entry(&self, name: &str) -> Option<TrashEntry> {
+        let text = fs::read_to_string(self.info_path(name)).ok()?;
+        let lines = text.lines().map(str::trim).skip_while(|l| *l != "[Trash Info]").skip(1).take_while(|l| !l.starts_with('['));
+        let (mut original, mut deleted_at) = (None, String::new());
+        for l in lines {
+            match l.split_once('=') {
+                Some(("Path", v)) => original = decode(v),
+                Some(("DeletionDate", v)) => deleted_at = v.to_string(),
+                _ => {}
+            }
+        }
+        let original = match (original?, &self.top) {
+            (p, _) if p.is_absolute() => p,
+            (p, Some(top)) => top.join(p),
+            (_, None) => return None,
+        };
+        let target = self.files().join(name);
+        let meta = fs::symlink_metadata(&target).ok()?;
+        Some(TrashEntry { id: target.to_string_lossy().to_string(), name: name.to_string(), original_path: original.to_string_lossy().to_string(), deleted_at, is_folder: meta.is_dir() })
+    }
+
+}
+
+fn tracked(conn: &Connection) -> Vec<String> {
+    let Ok(mut st) = conn.prepare("SELECT id FROM trash_entries ORDER BY trashed_at DESC") else { return vec![] };
+    st.query_map([], |r| r.get(0)).map(|rows| rows.filter_map(Result::ok).collect()).unwrap_or_default()
+}
+
+fn untrack(db: &Mutex<Connection>, id: &str) {
+    let _ = db.lock().unwrap().execute("DELETE FROM trash_entries WHERE id = ?", params![id]);
+}
+
+/// The trash dir and entry name behind an id returned by `list_trash`, which must be one Oxide trashed.
+fn locate(id: &str, db: &Mutex<Connection>) -> Result<(TrashDir, String), String> {
+    let known = db.lock().unwrap().query_row("SELECT 1 FROM trash_entries WHERE id = ?", params![id], |_| Ok(())).is_ok();
+    let p = Path::new(id);
+    let (files, name) = (p.parent().filter(|f| known && f.ends_with("files")).ok_or_else(|| format!("Not in the trash: {}", id))?, p.file_name().ok_or("Invalid trash entry")?);
+    let root = files.parent().ok_or("Invalid trash entry")?;
+    let top = root.file_name().is_some_and(|n| n.to_string_lossy().starts_with(".Trash-")).then(|| root.parent().map(Path::to_path_buf)).flatten();
+    Ok((TrashDir { root: root.to_path_buf(), top }, name.to_string_lossy().to_string()))
+}
+
+/// Moves `path` to the trash: the home trash when it can be renamed there, otherwise the trash at
+/// the top of its own mount (Linux), otherwise the home trash by copying.
+pub fn trash(path: &Path, data_dir: &Path, db: &Mutex<Connection>) -> Result<TrashEntry, String> {
+    let entry = put(path, data_dir)?;
+    db.lock().unwrap().execute("INSERT OR REPLACE INTO trash_entries (id, trashed_at) VALUES (?, ?)", params![entry.id, chrono::Utc::now().timestamp_millis()])
+        .map_err(|e| e.to_string())?;
+    Ok(entry)
+}
+
+fn put(path: &Path, data_dir: &Path) -> Result<TrashEntry, String> {
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    fs::symlink_metadata(&path).map_err(|e| e.to_string())?;
+    let home = home_trash(data_dir);
+    if home.root.starts_with(&path) || path.starts_with(&home.root) { return Err("Cannot move the trash into itself".into()); }
+    match home.put(&path, false) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {}
+        r => return r.map_err(|e| e.to_string()),
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(top) = mount_top(&path) {
+        if let Ok(entry) = top_trash(&top).put(&path, false) { return Ok(entry); }
+    }
+    home.put(&path, true).map_err(|e| e.to_string())
+}
+
+/// Entries Oxide moved to the trash, most recently deleted first. Entries removed from the trash
+/// by other means are forgotten.
+#[tauri::command]
+pub async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashEntry>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let ids = tracked(&db.lock().unwrap());
+        ids.iter().filter_map(|id| match locate(id, &db).ok().and_then(|(dir, name)| dir.entry(&name)) {
+            None => { untrack(&db, id); None }
+            entry => entry,
+        }).collect()
+    }).await.map_err(|e| e.to_string())
+}
+
+/// Moves an entry back to its original path, which must not exist again. Returns that path.
+pub fn restore(id: &str, db: &Mutex<Connection>) -> Result<String, String> {
+    let (dir, name) = locate(id, db)?;
+    let entry = dir.entry(&name).ok_or_else(|| format!("Not in the trash: {}", id))?;
+    let original = PathBuf::from(&entry.original_path);
+    if original.symlink_metadata().is_ok() { return Err(format!("{} already exists", entry.original_path)); }
+    if let Some(parent) = original.parent() { fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+    move_path(Path::new(id), &original).map_err(|e| e.to_string())?;
+    let _ = fs::remove_file(dir.info_path(&name));
+    untrack(db, id);
+    Ok(entry.original_path)
+}
+
+#[tauri::command]
+pub async fn restore_entry(state: State<'_, AppState>, id: String) -> Result<String, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || restore(&id, &db)).await.map_err(|e| e.to_string())?
+}
+
+/// Permanently deletes the given entries, or everything Oxide moved to the trash. Returns how many were removed.
+#[tauri::command]
+pub async fn empty_trash(state: State<'_, AppState>, ids: Option<Vec<String>>) -> Result<usize, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let ids = ids.unwrap_or_else(|| tracked(&db.lock().unwrap()));
+        let targets: Vec<(String, TrashDir, String)> = ids.into_iter().map(|id| locate(&id, &db).map(|(dir, name)| (id, dir, name))).collect::<Result<_, _>>()?;
+        let mut removed = 0;
+        for (id, dir, name) in targets {
+            match remove_tree(&dir.files().join(&name)) {
+                Ok(()) => removed += 1,
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("{}: {}", name, e)),
+            }
+            let _ = fs::remove_file(dir.info_path(&name));
+            untrack(&db, &id);
+        }
+        Ok(removed)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_escapes_everything_but_unreserved_characters_and_slashes() {
+        assert_eq!(encode(Path::new("/home/me/a-b_c.~!*'().txt")), "/home/me/a-b_c.~!*'().txt");
+        assert_eq!(encode(Path::new("/tmp/my file%.txt")), "/tmp/my%20file%25.txt");
+        assert_eq!(encode(Path::new("/tmp/é")), "/tmp/%C3%A9");
+    }
+
+    #[test]
+    fn decode_reverses_encode() {
+        for p in ["/tmp/my file%.txt", "/tmp/é/ü#?.md", "relative/a+b"] {
+            assert_eq!(decode(&encode(Path::new(p))), Some(PathBuf::from(p)));
+        }
+        assert_eq!(decode("/tmp/a%2fb"), Some(PathBuf::from("/tmp/a/b")));
+    }
+
+    #[test]
+    fn decode_rejects_broken_escapes() {
+        assert_eq!(decode("/tmp/%"), None);
+        assert_eq!(decode("/tmp/%4"), None);
+        assert_eq!(decode("/tmp/%zz"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+        let p = Path::new(std::ffi::OsStr::from_bytes(b"/tmp/caf\xE9"));
+        assert_eq!(encode(p), "/tmp/caf%E9");
+        assert_eq!(decode(&encode(p)).as_deref(), Some(p));
+    }
+}
@@ -0,0 +1,81 @@
+
+use crate::index::store::{ContentIndex, IndexStatus, Phase};
+use crate::state::AppState;
+use crate::watcher::watch_debounced;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const PROJECTS_TREE: &str = "tri_projects";
+const APPLY_INTERVAL: Duration = Duration::from_millis(500);
+
+fn watcher_id(root: &str) -> String { format!("index:{}", root) }
+
+/// Feeds file events for the project into the index, applying them once it is ready.
+/// Events arriving during a build or reconcile stay queued until the next batch after it.
+fn start_updates(app: &AppHandle, idx: &Arc<ContentIndex>) -> Result<(), String> {
+    let weak = Arc::downgrade(idx);
+    watch_debounced(app, watcher_id(&idx.root), std::slice::from_ref(&idx.root), APPLY_INTERVAL, move |paths| {
+        let Some(ix) = weak.upgrade() else { return };
+        ix.queue(paths);
+        if ix.phase() != Phase::Ready { return; }
+        if let Err(e) = ix.apply_pending() { eprintln!("[WARNING] Content index update failed for {}: {}", ix.root, e); }
+    })
+}
+
+/// Reopens the indexes built in earlier sessions and reconciles them with the disk in the background.
+pub fn restore_indexes(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(db) = state.lsm_db.lock().unwrap().clone() else { return };
+    let Ok(projects) = db.open_tree(PROJECTS_TREE) else { return };
+    for root in projects.iter().keys().filter_map(Result::ok).map(|k| String::from_utf8_lossy(&k).to_string()) {
+        let Ok(idx) = ContentIndex::open(&db, &root, Phase::Updating).map(Arc::new) else { continue };
+        state.indexes.lock().unwrap().insert(root.clone(), idx.clone());
+        if let Err(e) = start_updates(app, &idx) { eprintln!("[WARNING] Could not watch indexed project {}: {}", root, e); }
+        std::thread::spawn(move || {
+            if let Err(e) = idx.reconcile() { eprintln!("[WARNING] Content index reconcile failed for {}: {}", idx.root, e); }
+            idx.set_phase(Phase::Ready);
+            let _ = idx.apply_pending();
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn build_content_index(app: AppHandle, state: State<'_, AppState>, root: String) -> Result<IndexStatus, String> {
+    let db = state.lsm_db.lock().unwrap().clone().ok_or("LSM DB not initialized")?;
+    let idx = {
+        let mut map = state.indexes.lock().unwrap();
+        if let Some(existing) = map.get(&root) {
+            if existing.phase() != Phase::Ready { return Err(format!("Index for {} is already being updated", root)); }
+            existing.set_phase(Phase::Building);
+            existing.clone()
+        } else {
+            let idx = Arc::new(ContentIndex::open(&db, &root, Phase::Building)?);
+            map.insert(root.clone(), idx.clone());
+            idx
+        }
+    };
+    db.open_tree(PROJECTS_TREE).and_then(|t| t.insert(&root, &[])).map_err(|e| e.to_string())?;
+    if !state.watchers.lock().unwrap().contains_key(&watcher_id(&root)) { start_updates(&app, &idx)?; }
+    tauri::async_runtime::spawn_blocking(move || {
+        let res = idx.build();
+        idx.set_phase(Phase::Ready);
+        let _ = idx.apply_pending();
+        res.map(|_| idx.status())
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn content_index_status(state: State<'_, AppState>, root: String) -> Result<IndexStatus, String> {
+    Ok(match state.indexes.lock().unwrap().get(&root) { Some(idx) => idx.status(), None => IndexStatus::missing(root) })
+}
+
+#[tauri::command]
+pub fn drop_content_index(state: State<'_, AppState>, root: String) -> Result<(), String> {
+    let db = state.lsm_db.lock().unwrap().clone().ok_or("LSM DB not initialized")?;
+    state.indexes.lock().unwrap().remove(&root);
+    state.watchers.lock().unwrap().remove(&watcher_id(&root));
+    db.open_tree(PROJECTS_TREE).and_then(|t| t.remove(&root)).map_err(|e| e.to_string())?;
+    ContentIndex::drop_trees(&db, &root);
+    Ok(())
+}
@@ -0,0 +1,7 @@
+
+pub mod trigram;
+pub mod store;
+pub mod commands;
+
+pub use store::{ContentIndex, IndexStatus, Phase};
+pub use commands::{build_content_index, content_index_status, drop_content_index, restore_indexes};
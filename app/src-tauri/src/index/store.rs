@@ -0,0 +1,285 @@
+
+use crate::fs::encoding::decode_text;
+use crate::index::trigram::{trigrams, Trigram};
+use bincode::{config, decode_from_slice, encode_to_vec, Decode, Encode};
+use ignore::{WalkBuilder, WalkState};
+use serde::Serialize;
+use sled::{Db, Tree};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::sync_channel;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Posting key for files too large to index; they are candidates for every query.
+const UNINDEXED: &[u8] = b"\xff\xff\xff\xff";
+const MAX_INDEXED_SIZE: u64 = 8 * 1024 * 1024;
+/// Files buffered in memory during a build before their postings are merged into sled.
+const FLUSH_FILES: usize = 4096;
+
+#[derive(Encode, Decode)]
+struct FileEntry {
+    path: String,
+    mtime: i64,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase { Building, Updating, Ready }
+
+#[derive(Serialize)]
+pub struct IndexStatus {
+    pub root: String,
+    /// `None` when the project has no index.
+    pub phase: Option<Phase>,
+    pub files: usize,
+    pub trigrams: usize,
+    /// Files re-indexed since the last build; their old postings linger until the next rebuild.
+    pub stale_updates: u64,
+    pub pending: usize,
+    pub built_at: Option<i64>,
+}
+
+impl IndexStatus {
+    pub fn missing(root: String) -> Self { Self { root, phase: None, files: 0, trigrams: 0, stale_updates: 0, pending: 0, built_at: None } }
+}
+
+/// Trigram index of one project stored in sled. `post` holds the postings written by the last build and
+/// `delta` those added by incremental updates since; lookups union both. Postings are sorted file ids,
+/// delta-encoded, so a removed or changed file may leave stale ids that only cost an extra verification.
+pub struct ContentIndex {
+    pub root: String,
+    post: Tree,
+    delta: Tree,
+    files: Tree,
+    paths: Tree,
+    meta: Tree,
+    phase: Mutex<Phase>,
+    pending: Mutex<BTreeSet<PathBuf>>,
+}
+
+fn tree_name(root: &str, kind: &str) -> String { format!("tri:{}:{}", root, kind) }
+
+fn decode_ids(v: &[u8]) -> Vec<u32> {
+    let deltas: Vec<u32> = decode_from_slice(v, config::standard()).map(|r| r.0).unwrap_or_default();
+    deltas.iter().scan(0u32, |acc, d| { *acc += d; Some(*acc) }).collect()
+}
+
+fn encode_ids(ids: &[u32]) -> Result<Vec<u8>, String> {
+    let deltas: Vec<u32> = ids.iter().scan(0u32, |prev, &id| { let d = id - *prev; *prev = id; Some(d) }).collect();
+    encode_to_vec(&deltas, config::standard()).map_err(|e| e.to_string())
+}
+
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (mut i, mut j, mut out) = (0, 0, Vec::new());
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => { out.push(a[i]); i += 1; j += 1; }
+        }
+    }
+    out
+}
+
+fn mtime_of(meta: &std::fs::Metadata) -> i64 {
+    meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_millis() as i64).unwrap_or_default()
+}
+
+/// Trigrams of a file's searchable text, decoded the same way the search decodes it.
+fn file_trigrams(path: &Path, size: u64) -> Option<BTreeSet<Trigram>> {
+    if size > MAX_INDEXED_SIZE { return None; }
+    let bytes = std::fs::read(path).ok()?;
+    let text = decode_text(&bytes).map(|d| d.text).unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned());
+    Some(trigrams(text.as_bytes()))
+}
+
+/// Files directly inside `dir` that a walk from `root` would visit, so updates honor every ignore file on the way.
+pub fn visible_files(root: &str, dir: &Path) -> HashSet<PathBuf> {
+    let d = dir.to_path_buf();
+    WalkBuilder::new(root).filter_entry(move |e| d.starts_with(e.path()) || e.path().parent() == Some(d.as_path())).build()
+        .filter_map(Result::ok).filter(|e| e.file_type().is_some_and(|t| t.is_file()) && e.path().parent() == Some(dir))
+        .map(|e| e.into_path()).collect()
+}
+
+/// Files below `dir` that a walk from `root` would visit, honoring the ignore files above `dir` too.
+pub fn visible_files_below(root: &str, dir: &Path) -> Vec<PathBuf> {
+    let d = dir.to_path_buf();
+    WalkBuilder::new(root).filter_entry(move |e| d.starts_with(e.path()) || e.path().starts_with(&d)).build()
+        .filter_map(Result::ok).filter(|e| e.file_type().is_some_and(|t| t.is_file()) && e.path().starts_with(dir))
+        .map(|e| e.into_path()).collect()
+}
+
+impl ContentIndex {
+    pub fn open(db: &Db, root: &str, phase: Phase) -> Result<Self, String> {
+        let t = |kind: &str| db.open_tree(tree_name(root, kind)).map_err(|e| e.to_string());
+        Ok(Self { root: root.to_string(), post: t("post")?, delta: t("delta")?, files: t("files")?, paths: t("paths")?, meta: t("meta")?, phase: Mutex::new(phase), pending: Mutex::new(BTreeSet::new()) })
+    }
+
+    pub fn drop_trees(db: &Db, root: &str) {
+        for kind in ["post", "delta", "files", "paths", "meta"] { let _ = db.drop_tree(tree_name(root, kind)); }
+    }
+
+    pub fn phase(&self) -> Phase { *self.phase.lock().unwrap() }
+
+    pub fn set_phase(&self, p: Phase) { *self.phase.lock().unwrap() = p; }
+
+    fn meta_i64(&self, key: &str) -> Option<i64> {
+        self.meta.get(key).ok().flatten().and_then(|v| v.as_ref().try_into().ok()).map(i64::from_be_bytes)
+    }
+
+    fn set_meta_i64(&self, key: &str, v: i64) { let _ = self.meta.insert(key, &v.to_be_bytes()); }
+
+    fn merge_postings(tree: &Tree, map: HashMap<Vec<u8>, Vec<u32>>) -> Result<(), String> {
+        for (key, mut ids) in map {
+            if let Some(v) = tree.get(&key).map_err(|e| e.to_string())? { ids.extend(decode_ids(&v)); }
+            ids.sort_unstable();
+            ids.dedup();
+            tree.insert(key, encode_ids(&ids)?).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn store_entry(&self, path: &str, id: u32, meta: &std::fs::Metadata) -> Result<(), String> {
+        let entry = FileEntry { path: path.to_string(), mtime: mtime_of(meta), size: meta.len() };
+        self.files.insert(id.to_be_bytes(), encode_to_vec(&entry, config::standard()).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        self.paths.insert(path, &id.to_be_bytes()).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn next_id(&self) -> u32 {
+        let id = self.meta_i64("next_id").unwrap_or_default();
+        self.set_meta_i64("next_id", id + 1);
+        id as u32
+    }
+
+    /// Re-indexes the whole project from scratch.
+    pub fn build(&self) -> Result<(), String> {
+        for t in [&self.post, &self.delta, &self.files, &self.paths, &self.meta] { t.clear().map_err(|e| e.to_string())?; }
+        let (tx, rx) = sync_channel::<(PathBuf, std::fs::Metadata, Option<BTreeSet<Trigram>>)>(256);
+        std::thread::scope(|s| {
+            let root = self.root.clone();
+            s.spawn(move || WalkBuilder::new(&root).threads(num_cpus::get()).build_parallel().run(|| {
+                let tx = tx.clone();
+                Box::new(move |entry| {
+                    let Ok(entry) = entry else { return WalkState::Continue };
+                    if !entry.file_type().is_some_and(|t| t.is_file()) { return WalkState::Continue; }
+                    let Ok(meta) = entry.metadata() else { return WalkState::Continue };
+                    let tris = file_trigrams(entry.path(), meta.len());
+                    if tx.send((entry.into_path(), meta, tris)).is_err() { return WalkState::Quit; }
+                    WalkState::Continue
+                })
+            }));
+            let (mut postings, mut buffered): (HashMap<Vec<u8>, Vec<u32>>, usize) = (HashMap::new(), 0);
+            for (path, meta, tris) in rx {
+                let id = self.next_id();
+                self.store_entry(&path.to_string_lossy(), id, &meta)?;
+                match tris {
+                    Some(tris) => for t in tris { postings.entry(t.to_vec()).or_default().push(id); },
+                    None => postings.entry(UNINDEXED.to_vec()).or_default().push(id),
+                }
+                buffered += 1;
+                if buffered >= FLUSH_FILES { Self::merge_postings(&self.post, std::mem::take(&mut postings))?; buffered = 0; }
+            }
+            Self::merge_postings(&self.post, postings)
+        })?;
+        self.set_meta_i64("built_at", chrono::Utc::now().timestamp_millis());
+        self.set_meta_i64("stale", 0);
+        self.post.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn index_file(&self, path: &Path, meta: &std::fs::Metadata) -> Result<(), String> {
+        let key = path.to_string_lossy().to_string();
+        let existing = self.paths.get(&key).map_err(|e| e.to_string())?.and_then(|v| v.as_ref().try_into().ok()).map(u32::from_be_bytes);
+        let id = match existing {
+            Some(id) => { self.set_meta_i64("stale", self.meta_i64("stale").unwrap_or_default() + 1); id }
+            None => self.next_id(),
+        };
+        self.store_entry(&key, id, meta)?;
+        let mut postings: HashMap<Vec<u8>, Vec<u32>> = HashMap::new();
+        match file_trigrams(path, meta.len()) {
+            Some(tris) => for t in tris { postings.insert(t.to_vec(), vec![id]); },
+            None => { postings.insert(UNINDEXED.to_vec(), vec![id]); }
+        }
+        Self::merge_postings(&self.delta, postings)
+    }
+
+    /// Forgets `path` and, if it was a directory, every file below it.
+    fn remove_path(&self, path: &Path) -> Result<(), String> {
+        let key = path.to_string_lossy().to_string();
+        let mut keys = vec![key.clone()];
+        keys.extend(self.paths.scan_prefix(format!("{}{}", key, std::path::MAIN_SEPARATOR)).keys().filter_map(Result::ok).map(|k| String::from_utf8_lossy(&k).to_string()));
+        for k in keys {
+            if let Some(id) = self.paths.remove(&k).map_err(|e| e.to_string())? { self.files.remove(id).map_err(|e| e.to_string())?; }
+        }
+        Ok(())
+    }
+
+    pub fn queue(&self, paths: Vec<PathBuf>) { self.pending.lock().unwrap().extend(paths); }
+
+    /// Applies watcher events queued since the last call.
+    pub fn apply_pending(&self) -> Result<(), String> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut by_dir: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for p in pending { if let Some(dir) = p.parent() { by_dir.entry(dir.to_path_buf()).or_default().push(p); } }
+        for (dir, paths) in by_dir {
+            let visible = visible_files(&self.root, &dir);
+            for p in paths {
+                match std::fs::metadata(&p) {
+                    Ok(meta) if visible.contains(&p) => self.index_file(&p, &meta)?,
+                    Ok(meta) if meta.is_dir() => {}
+                    _ => self.remove_path(&p)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Catches up with changes made while Oxide was not watching, comparing mtime and size.
+    pub fn reconcile(&self) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        for entry in WalkBuilder::new(&self.root).build().filter_map(Result::ok) {
+            if !entry.file_type().is_some_and(|t| t.is_file()) { continue; }
+            let Ok(meta) = entry.metadata() else { continue };
+            let key = entry.path().to_string_lossy().to_string();
+            let stored = self.paths.get(&key).ok().flatten().and_then(|id| self.files.get(id).ok().flatten())
+                .and_then(|v| decode_from_slice::<FileEntry, _>(&v, config::standard()).ok()).map(|r| r.0);
+            if !stored.is_some_and(|e| e.mtime == mtime_of(&meta) && e.size == meta.len()) { self.index_file(entry.path(), &meta)?; }
+            seen.insert(key);
+        }
+        let gone: Vec<String> = self.paths.iter().keys().filter_map(Result::ok).map(|k| String::from_utf8_lossy(&k).to_string()).filter(|k| !seen.contains(k)).collect();
+        for k in gone { if let Some(id) = self.paths.remove(&k).map_err(|e| e.to_string())? { self.files.remove(id).map_err(|e| e.to_string())?; } }
+        Ok(())
+    }
+
+    fn lookup(&self, key: &[u8]) -> Vec<u32> {
+        let mut ids: Vec<u32> = [&self.post, &self.delta].iter().filter_map(|t| t.get(key).ok().flatten()).flat_map(|v| decode_ids(&v)).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Paths of files that may contain all `tris`, or `None` while the index is not ready to answer,
+    /// including while watcher events it has not applied yet are queued.
+    pub fn candidates(&self, tris: &[Trigram]) -> Option<Vec<String>> {
+        if self.phase() != Phase::Ready || !self.pending.lock().unwrap().is_empty() { return None; }
+        let mut lists: Vec<Vec<u32>> = tris.iter().map(|t| self.lookup(t)).collect();
+        lists.sort_by_key(|l| l.len());
+        let mut ids = lists.first().cloned().unwrap_or_default();
+        for l in &lists[1..] { if ids.is_empty() { break; } ids = intersect(&ids, l); }
+        ids.extend(self.lookup(UNINDEXED));
+        ids.sort_unstable();
+        ids.dedup();
+        Some(ids.into_iter().filter_map(|id| self.files.get(id.to_be_bytes()).ok().flatten())
+            .filter_map(|v| decode_from_slice::<FileEntry, _>(&v, config::standard()).ok()).map(|r| r.0.path).collect())
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        IndexStatus {
+            root: self.root.clone(), phase: Some(self.phase()), files: self.files.len(), trigrams: self.post.len() + self.delta.len(),
+            stale_updates: self.meta_i64("stale").unwrap_or_default() as u64, pending: self.pending.lock().unwrap().len(), built_at: self.meta_i64("built_at"),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+
+use regex_syntax::hir::{Hir, HirKind};
+use std::collections::BTreeSet;
+
+pub type Trigram = [u8; 3];
+
+/// Distinct trigrams of `text`, with ASCII folded to lowercase so one index serves both case modes.
+pub fn trigrams(text: &[u8]) -> BTreeSet<Trigram> {
+    text.windows(3).map(|w| [w[0].to_ascii_lowercase(), w[1].to_ascii_lowercase(), w[2].to_ascii_lowercase()]).collect()
+}
+
+/// Trigrams every matching file must contain, or `None` when the query has no literal of three bytes or
+/// more to narrow on. Case-insensitive queries drop trigrams with non-ASCII bytes, which fold differently.
+pub fn required_trigrams(query: &str, regex: bool, case_sensitive: bool) -> Option<Vec<Trigram>> {
+    let literals = if regex {
+        let hir = regex_syntax::Parser::new().parse(query).ok()?;
+        let mut out = Vec::new();
+        collect_literals(&hir, &mut out);
+        out
+    } else { vec![query.as_bytes().to_vec()] };
+    let set: BTreeSet<Trigram> = literals.iter().flat_map(|l| trigrams(l))
+        .filter(|t| case_sensitive || t.iter().all(u8::is_ascii)).collect();
+    (!set.is_empty()).then(|| set.into_iter().collect())
+}
+
+/// Literal runs that must appear in any match. Alternations and optional parts contribute nothing.
+fn collect_literals(hir: &Hir, out: &mut Vec<Vec<u8>>) {
+    match hir.kind() {
+        HirKind::Literal(l) => out.push(l.0.to_vec()),
+        HirKind::Capture(c) => collect_literals(&c.sub, out),
+        HirKind::Repetition(r) if r.min > 0 => collect_literals(&r.sub, out),
+        HirKind::Concat(subs) => {
+            let mut run = Vec::new();
+            for sub in subs {
+                match sub.kind() {
+                    HirKind::Literal(l) => run.extend_from_slice(&l.0),
+                    HirKind::Look(_) => {}
+                    _ => {
+                        if !run.is_empty() { out.push(std::mem::take(&mut run)); }
+                        collect_literals(sub, out);
+                    }
+                }
+            }
+            if !run.is_empty() { out.push(run); }
+        }
+        _ => {}
+    }
+}
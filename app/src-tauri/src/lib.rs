@@ -2,7 +2,9 @@
 pub mod binary;
 pub mod db;
 pub mod diff;
+pub mod finder;
 pub mod fs;
+pub mod index;
 pub mod lsm;
 pub mod mmap_viewer;
 pub mod plugin_system;
@@ -10,6 +12,7 @@ pub mod pty;
 pub mod scan_dir;
 pub mod search;
 pub mod state;
+pub mod symbols;
 pub mod watcher;
 pub mod workspace;
 pub mod setup;
@@ -31,6 +34,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(commands::register())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| if let tauri::RunEvent::Exit = event { setup::shutdown(app) });
 }
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    #[cfg(unix)]
+    if let (Some("--pty-host"), Some(sock)) = (std::env::args().nth(1).as_deref(), std::env::args().nth(2)) {
+        return project_ai_lib::pty::host::run_host(std::path::Path::new(&sock));
+    }
     project_ai_lib::run()
 }
@@ -0,0 +1,131 @@
+
+use crate::pty::markers::ShellTracker;
+use crate::pty::models::PtySession;
+use crate::state::AppState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BroadcastGroup {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+/// Outcome of a broadcast for one session. `idle` and `exit_code` are only set when the caller waited.
+#[derive(Serialize, Clone)]
+pub struct Delivery {
+    pub id: String,
+    pub delivered: bool,
+    pub error: Option<String>,
+    pub idle: Option<bool>,
+    pub exit_code: Option<i32>,
+}
+
+impl Delivery {
+    fn failed(id: String, error: String) -> Self { Self { id, delivered: false, error: Some(error), idle: None, exit_code: None } }
+}
+
+/// Writes `data` to every target, holding the global session lock only long enough to look each one up.
+/// With `wait` set, blocks until each delivered target shows a fresh prompt or the timeout expires.
+pub fn deliver(sessions: &Mutex<HashMap<String, PtySession>>, ids: Vec<String>, data: &str, wait: Option<Duration>) -> Vec<Delivery> {
+    let mut sent: Vec<(Delivery, Arc<Mutex<ShellTracker>>, u64, i64)> = Vec::new();
+    let mut res = Vec::new();
+    for id in ids {
+        let target: Option<(SharedWriter, Arc<Mutex<ShellTracker>>)> = sessions.lock().unwrap().get(&id).map(|s| (s.writer.clone(), s.shell.clone()));
+        let Some((writer, shell)) = target else { res.push(Delivery::failed(id, "No session found".into())); continue };
+        let (prompts, sent_at) = (shell.lock().unwrap().prompts, chrono::Utc::now().timestamp_millis());
+        let written = { let mut w = writer.lock().unwrap(); w.write_all(data.as_bytes()).and_then(|_| w.flush()) };
+        match written {
+            Ok(()) => sent.push((Delivery { id, delivered: true, error: None, idle: None, exit_code: None }, shell, prompts, sent_at)),
+            Err(e) => res.push(Delivery::failed(id, e.to_string())),
+        }
+    }
+    let Some(wait) = wait else { res.extend(sent.into_iter().map(|s| s.0)); return res };
+    let deadline = Instant::now() + wait;
+    loop {
+        let mut pending = false;
+        for (d, shell, prompts, sent_at) in sent.iter_mut().filter(|s| s.0.idle.is_none() && s.0.error.is_none()) {
+            let sh = shell.lock().unwrap();
+            if !sh.integrated { d.error = Some("Shell integration is not active, cannot detect the prompt".into()); continue; }
+            if sh.idle && sh.prompts > *prompts {
+                d.idle = Some(true);
+                d.exit_code = sh.history.back().filter(|c| c.started_at >= *sent_at).and_then(|c| c.exit_code);
+            } else { pending = true; }
+        }
+        if !pending { break; }
+        if Instant::now() >= deadline {
+            for (d, ..) in sent.iter_mut().filter(|s| s.0.idle.is_none() && s.0.error.is_none()) { d.idle = Some(false); d.error = Some("Timed out waiting for the prompt".into()); }
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    res.extend(sent.into_iter().map(|s| s.0));
+    res
+}
+
+fn load_group(conn: &Connection, name: &str) -> Result<Option<BroadcastGroup>, String> {
+    conn.query_row("SELECT name, members FROM broadcast_groups WHERE name = ?", params![name], |r| {
+        let members: String = r.get(1)?;
+        Ok(BroadcastGroup { name: r.get(0)?, members: serde_json::from_str(&members).unwrap_or_default() })
+    }).optional().map_err(|e| e.to_string())
+}
+
+fn store_group(conn: &Connection, g: &BroadcastGroup) -> Result<(), String> {
+    let members = serde_json::to_string(&g.members).map_err(|e| e.to_string())?;
+    conn.execute("INSERT OR REPLACE INTO broadcast_groups (name, members) VALUES (?, ?)", params![g.name, members]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn create_broadcast_group(state: tauri::State<'_, AppState>, name: String, members: Vec<String>) -> Result<BroadcastGroup, String> {
+    if name.is_empty() { return Err("Group name is required".into()); }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    if load_group(&conn, &name)?.is_some() { return Err(format!("Broadcast group already exists: {}", name)); }
+    let mut g = BroadcastGroup { name, members: Vec::new() };
+    for m in members { if !g.members.contains(&m) { g.members.push(m); } }
+    store_group(&conn, &g)?;
+    Ok(g)
+}
+
+#[tauri::command]
+pub fn list_broadcast_groups(state: tauri::State<'_, AppState>) -> Result<Vec<BroadcastGroup>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut st = conn.prepare("SELECT name, members FROM broadcast_groups ORDER BY name").map_err(|e| e.to_string())?;
+    let rows = st.query_map([], |r| {
+        let members: String = r.get(1)?;
+        Ok(BroadcastGroup { name: r.get(0)?, members: serde_json::from_str(&members).unwrap_or_default() })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn update_broadcast_group(state: tauri::State<'_, AppState>, name: String, add: Option<Vec<String>>, remove: Option<Vec<String>>) -> Result<BroadcastGroup, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut g = load_group(&conn, &name)?.ok_or_else(|| format!("Unknown broadcast group: {}", name))?;
+    let remove = remove.unwrap_or_default();
+    g.members.retain(|m| !remove.contains(m));
+    for m in add.unwrap_or_default() { if !g.members.contains(&m) { g.members.push(m); } }
+    store_group(&conn, &g)?;
+    Ok(g)
+}
+
+#[tauri::command]
+pub fn delete_broadcast_group(state: tauri::State<'_, AppState>, name: String) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM broadcast_groups WHERE name = ?", params![name]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn broadcast_to_group(state: tauri::State<'_, AppState>, name: String, data: String, wait_idle_ms: Option<u64>) -> Result<Vec<Delivery>, String> {
+    let group = load_group(&*state.db.lock().map_err(|e| e.to_string())?, &name)?.ok_or_else(|| format!("Unknown broadcast group: {}", name))?;
+    let sessions = state.sessions.clone();
+    tauri::async_runtime::spawn_blocking(move || deliver(&sessions, group.members, &data, wait_idle_ms.map(Duration::from_millis)))
+        .await.map_err(|e| e.to_string())
+}
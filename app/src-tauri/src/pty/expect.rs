@@ -0,0 +1,94 @@
+
+use crate::pty::scrollback::for_each_plain_line;
+use crate::pty::stream::OutputStream;
+use crate::state::AppState;
+use regex::Regex;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const CONTEXT_BYTES: usize = 4096;
+const DEFAULT_RUN_TIMEOUT: u64 = 30_000;
+
+#[derive(Serialize)]
+pub struct ExpectMatch {
+    pub matched: String,
+    pub groups: Vec<Option<String>>,
+    pub before: String,
+    pub after: String,
+}
+
+type Handles = (Arc<OutputStream>, Arc<Mutex<Vec<u8>>>, Arc<Mutex<Box<dyn Write + Send>>>);
+
+fn handles(state: &AppState, id: &str) -> Result<Handles, String> {
+    let map = state.sessions.lock().unwrap();
+    let s = map.get(id).ok_or("No session found")?;
+    Ok((s.output.clone(), s.buffer.clone(), s.writer.clone()))
+}
+
+fn plain_text(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+    for_each_plain_line(bytes, |_, l| { lines.push(l.to_string()); true });
+    lines.join("\n")
+}
+
+fn tail(s: &str, max: usize) -> &str {
+    let mut i = s.len().saturating_sub(max);
+    while !s.is_char_boundary(i) { i += 1; }
+    &s[i..]
+}
+
+fn head(s: &str, max: usize) -> &str {
+    let mut i = s.len().min(max);
+    while !s.is_char_boundary(i) { i -= 1; }
+    &s[..i]
+}
+
+/// Polls the output written after `start` (escape sequences stripped) until `re` matches.
+fn wait_for(out: &OutputStream, buffer: &Mutex<Vec<u8>>, re: &Regex, start: u64, timeout: Duration) -> Result<ExpectMatch, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (bytes, closed) = out.since(start, buffer);
+        let text = plain_text(&bytes);
+        if let Some(c) = re.captures(&text) {
+            let m = c.get(0).unwrap();
+            return Ok(ExpectMatch {
+                matched: m.as_str().to_string(),
+                groups: c.iter().skip(1).map(|g| g.map(|g| g.as_str().to_string())).collect(),
+                before: tail(&text[..m.start()], CONTEXT_BYTES).to_string(),
+                after: head(&text[m.end()..], CONTEXT_BYTES).to_string(),
+            });
+        }
+        if closed { return Err("Session exited before the pattern matched".into()); }
+        if Instant::now() >= deadline { return Err(format!("Timed out waiting for /{}/", re.as_str())); }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Waits for `pattern` to match output produced after the call.
+#[tauri::command]
+pub async fn pty_expect(state: tauri::State<'_, AppState>, id: String, pattern: String, timeout_ms: u64) -> Result<ExpectMatch, String> {
+    let re = Regex::new(&pattern).map_err(|e| e.to_string())?;
+    let (out, buffer, _) = handles(&state, &id)?;
+    let start = out.written();
+    tauri::async_runtime::spawn_blocking(move || wait_for(&out, &buffer, &re, start, Duration::from_millis(timeout_ms)))
+        .await.map_err(|e| e.to_string())?
+}
+
+/// Types `command` followed by Enter, then waits for `until_pattern` in the output it produces.
+/// The echoed command line is part of that output, so the pattern should not match the command itself.
+#[tauri::command]
+pub async fn pty_run(state: tauri::State<'_, AppState>, id: String, command: String, until_pattern: String, timeout_ms: Option<u64>) -> Result<ExpectMatch, String> {
+    let re = Regex::new(&until_pattern).map_err(|e| e.to_string())?;
+    let (out, buffer, writer) = handles(&state, &id)?;
+    let start = out.written();
+    {
+        let mut w = writer.lock().unwrap();
+        w.write_all(format!("{}\r", command).as_bytes()).map_err(|e| e.to_string())?;
+        w.flush().map_err(|e| e.to_string())?;
+    }
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_RUN_TIMEOUT));
+    tauri::async_runtime::spawn_blocking(move || wait_for(&out, &buffer, &re, start, timeout))
+        .await.map_err(|e| e.to_string())?
+}
@@ -0,0 +1,99 @@
+
+use crate::pty::host::protocol::{HostRequest, HostResponse, HostedSession};
+use crate::pty::models::ShellProfile;
+use portable_pty::{Child, MasterPty};
+use std::path::PathBuf;
+
+pub type AttachedPty = (Box<dyn MasterPty + Send>, Box<dyn Child + Send>, Vec<u8>);
+
+/// Handle to the detached PTY host process; every request opens a short-lived socket connection.
+#[derive(Debug, Clone)]
+pub struct HostClient {
+    sock: PathBuf,
+}
+
+#[cfg(unix)]
+impl HostClient {
+    pub fn connect_or_start(sock: PathBuf) -> Result<Self, String> {
+        use std::os::unix::process::CommandExt;
+        use std::process::Stdio;
+        let client = Self { sock };
+        match client.hello() {
+            Ok(true) => return Ok(client),
+            // a host left running by another build: stop it, or at least take over its socket path
+            Ok(false) => {
+                if client.request(&HostRequest::Shutdown).is_err() { let _ = std::fs::remove_file(&client.sock); }
+                for _ in 0..50 {
+                    if std::os::unix::net::UnixStream::connect(&client.sock).is_err() { break; }
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+            }
+            Err(_) => {}
+        }
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let mut child = std::process::Command::new(exe).arg("--pty-host").arg(&client.sock)
+            .stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null())
+            .process_group(0).spawn().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || { let _ = child.wait(); });
+        for _ in 0..50 {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            if client.hello() == Ok(true) { return Ok(client); }
+        }
+        Err("PTY host did not start".into())
+    }
+
+    /// Whether the host answering on the socket speaks this build's protocol; `Err` when none answers.
+    fn hello(&self) -> Result<bool, String> {
+        use crate::pty::host::protocol::{read_line_raw, write_message, PROTOCOL_VERSION};
+        let mut s = std::os::unix::net::UnixStream::connect(&self.sock).map_err(|e| e.to_string())?;
+        write_message(&mut s, &HostRequest::Hello { version: PROTOCOL_VERSION }).map_err(|e| e.to_string())?;
+        let line = read_line_raw(&mut s).map_err(|e| e.to_string())?;
+        Ok(matches!(serde_json::from_str(&line), Ok(HostResponse::Hello { version }) if version == PROTOCOL_VERSION))
+    }
+
+    pub fn request(&self, req: &HostRequest) -> Result<HostResponse, String> {
+        use crate::pty::host::protocol::{read_line_raw, write_message};
+        let mut s = std::os::unix::net::UnixStream::connect(&self.sock).map_err(|e| e.to_string())?;
+        write_message(&mut s, req).map_err(|e| e.to_string())?;
+        match serde_json::from_str(&read_line_raw(&mut s).map_err(|e| e.to_string())?).map_err(|e| e.to_string())? {
+            HostResponse::Error { message } => Err(message),
+            res => Ok(res),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<HostedSession>, String> {
+        match self.request(&HostRequest::List)? { HostResponse::Sessions { sessions } => Ok(sessions), _ => Err("Unexpected host response".into()) }
+    }
+
+    pub fn spawn(&self, id: &str, cwd: Option<String>, rows: u16, cols: u16, profile: &ShellProfile) -> Result<AttachedPty, String> {
+        self.request(&HostRequest::Spawn { id: id.to_string(), cwd, rows, cols, profile: profile.clone() })?;
+        self.attach(id)
+    }
+
+    pub fn attach(&self, id: &str) -> Result<AttachedPty, String> {
+        use crate::pty::host::protocol::{read_line_raw, write_message};
+        use crate::pty::host::remote::{RemoteChild, RemoteMaster};
+        use std::io::Read;
+        let mut s = std::os::unix::net::UnixStream::connect(&self.sock).map_err(|e| e.to_string())?;
+        write_message(&mut s, &HostRequest::Attach { id: id.to_string() }).map_err(|e| e.to_string())?;
+        let (pid, replay) = match serde_json::from_str(&read_line_raw(&mut s).map_err(|e| e.to_string())?).map_err(|e| e.to_string())? {
+            HostResponse::Attached { pid, replay } => (pid, replay),
+            HostResponse::Error { message } => return Err(message),
+            _ => return Err("Unexpected host response".into()),
+        };
+        let mut buf = vec![0u8; replay];
+        s.read_exact(&mut buf).map_err(|e| e.to_string())?;
+        let master = RemoteMaster { client: self.clone(), id: id.to_string(), stream: s };
+        let child = RemoteChild { client: self.clone(), id: id.to_string(), pid };
+        Ok((Box::new(master), Box::new(child), buf))
+    }
+}
+
+#[cfg(not(unix))]
+impl HostClient {
+    pub fn connect_or_start(_sock: PathBuf) -> Result<Self, String> { Err("PTY host is only available on Unix".into()) }
+    pub fn request(&self, _req: &HostRequest) -> Result<HostResponse, String> { Err("PTY host is only available on Unix".into()) }
+    pub fn list(&self) -> Result<Vec<HostedSession>, String> { Ok(vec![]) }
+    pub fn spawn(&self, _id: &str, _cwd: Option<String>, _rows: u16, _cols: u16, _profile: &ShellProfile) -> Result<AttachedPty, String> { Err("PTY host is only available on Unix".into()) }
+    pub fn attach(&self, _id: &str) -> Result<AttachedPty, String> { Err("PTY host is only available on Unix".into()) }
+}
@@ -0,0 +1,12 @@
+
+pub mod protocol;
+pub mod client;
+#[cfg(unix)]
+pub mod server;
+#[cfg(unix)]
+pub mod remote;
+
+pub use client::HostClient;
+pub use protocol::HostedSession;
+#[cfg(unix)]
+pub use server::run_host;
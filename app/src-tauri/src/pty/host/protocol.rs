@@ -0,0 +1,64 @@
+
+use crate::pty::models::ShellProfile;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// Bumped whenever requests or responses change shape; a host speaking another version is replaced.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HostRequest {
+    /// Sent first by a client to check it speaks the same protocol as the running host.
+    Hello { version: u32 },
+    /// Kills every session and stops the host.
+    Shutdown,
+    Spawn { id: String, cwd: Option<String>, rows: u16, cols: u16, profile: ShellProfile },
+    Attach { id: String },
+    Resize { id: String, rows: u16, cols: u16 },
+    Size { id: String },
+    ProcessGroup { id: String },
+    Status { id: String },
+    Kill { id: String },
+    List,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HostResponse {
+    Ok,
+    Hello { version: u32 },
+    Error { message: String },
+    Spawned { pid: Option<u32> },
+    /// Followed on the same stream by `replay` bytes of scrollback, then live output.
+    Attached { pid: Option<u32>, replay: usize },
+    Size { rows: u16, cols: u16 },
+    ProcessGroup { pgid: Option<i32> },
+    Status { exited: bool, code: u32, signal: Option<String> },
+    Sessions { sessions: Vec<HostedSession> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HostedSession {
+    pub id: String,
+    pub pid: Option<u32>,
+    pub alive: bool,
+}
+
+/// Reads one `\n`-terminated line without buffering past it, so raw bytes that follow stay on the stream.
+pub fn read_line_raw(r: &mut impl Read) -> std::io::Result<String> {
+    let (mut line, mut b) = (Vec::new(), [0u8; 1]);
+    loop {
+        if r.read(&mut b)? == 0 { return Err(std::io::ErrorKind::UnexpectedEof.into()); }
+        if b[0] == b'\n' { break; }
+        line.push(b[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
+pub fn write_message<T: Serialize>(w: &mut impl Write, msg: &T) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(msg).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    w.write_all(&line)?;
+    w.flush()
+}
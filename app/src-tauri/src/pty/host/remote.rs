@@ -0,0 +1,72 @@
+
+use crate::pty::host::client::HostClient;
+use crate::pty::host::protocol::{HostRequest, HostResponse};
+use portable_pty::{Child, ChildKiller, ExitStatus, MasterPty, PtySize};
+use std::os::unix::net::UnixStream;
+
+/// Master side of a PTY that lives in the host process; I/O goes over the attach stream.
+pub struct RemoteMaster {
+    pub(crate) client: HostClient,
+    pub(crate) id: String,
+    pub(crate) stream: UnixStream,
+}
+
+impl MasterPty for RemoteMaster {
+    fn resize(&self, size: PtySize) -> anyhow::Result<()> {
+        self.client.request(&HostRequest::Resize { id: self.id.clone(), rows: size.rows, cols: size.cols }).map(|_| ()).map_err(anyhow::Error::msg)
+    }
+
+    fn get_size(&self) -> anyhow::Result<PtySize> {
+        match self.client.request(&HostRequest::Size { id: self.id.clone() }).map_err(anyhow::Error::msg)? {
+            HostResponse::Size { rows, cols } => Ok(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 }),
+            _ => Err(anyhow::anyhow!("Unexpected host response")),
+        }
+    }
+
+    fn try_clone_reader(&self) -> anyhow::Result<Box<dyn std::io::Read + Send>> { Ok(Box::new(self.stream.try_clone()?)) }
+
+    fn take_writer(&self) -> anyhow::Result<Box<dyn std::io::Write + Send>> { Ok(Box::new(self.stream.try_clone()?)) }
+
+    fn process_group_leader(&self) -> Option<i32> {
+        match self.client.request(&HostRequest::ProcessGroup { id: self.id.clone() }) { Ok(HostResponse::ProcessGroup { pgid }) => pgid, _ => None }
+    }
+
+    fn as_raw_fd(&self) -> Option<std::os::unix::io::RawFd> { None }
+
+    fn tty_name(&self) -> Option<std::path::PathBuf> { None }
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteChild {
+    pub(crate) client: HostClient,
+    pub(crate) id: String,
+    pub(crate) pid: Option<u32>,
+}
+
+impl ChildKiller for RemoteChild {
+    fn kill(&mut self) -> std::io::Result<()> {
+        self.client.request(&HostRequest::Kill { id: self.id.clone() }).map(|_| ()).map_err(std::io::Error::other)
+    }
+
+    fn clone_killer(&self) -> Box<dyn ChildKiller + Send + Sync> { Box::new(self.clone()) }
+}
+
+impl Child for RemoteChild {
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        match self.client.request(&HostRequest::Status { id: self.id.clone() }).map_err(std::io::Error::other)? {
+            HostResponse::Status { exited: false, .. } => Ok(None),
+            HostResponse::Status { signal: Some(sig), .. } => Ok(Some(ExitStatus::with_signal(&sig))),
+            HostResponse::Status { code, .. } => Ok(Some(ExitStatus::with_exit_code(code))),
+            _ => Err(std::io::Error::other("Unexpected host response")),
+        }
+    }
+
+    fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        loop {
+            if let Some(st) = self.try_wait()? { return Ok(st); }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+
+    fn process_id(&self) -> Option<u32> { self.pid }
+}
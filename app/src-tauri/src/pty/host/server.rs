@@ -0,0 +1,182 @@
+
+use crate::pty::host::protocol::{read_line_raw, write_message, HostRequest, HostResponse, HostedSession, PROTOCOL_VERSION};
+use crate::pty::reader::BUFFER_LIMIT;
+use crate::pty::spawner::create_pty;
+use portable_pty::{Child, MasterPty, NativePtySystem, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const IDLE_EXIT: Duration = Duration::from_secs(60);
+
+/// Path and inode of the socket this host bound.
+static SOCKET: OnceLock<(PathBuf, u64)> = OnceLock::new();
+
+struct Mirror {
+    buffer: Vec<u8>,
+    client: Option<UnixStream>,
+}
+
+struct HostSession {
+    master: Box<dyn MasterPty + Send>,
+    child: Box<dyn Child + Send>,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    mirror: Arc<Mutex<Mirror>>,
+}
+
+type HostSessions = Arc<Mutex<HashMap<String, HostSession>>>;
+
+/// Entry point of the detached `--pty-host` process. Owns the PTYs so they outlive the UI.
+pub fn run_host(sock: &Path) {
+    let _ = std::fs::remove_file(sock);
+    // the socket must never be reachable by other users, not even between bind and chmod
+    let mask = unsafe { libc::umask(0o177) };
+    let bound = UnixListener::bind(sock);
+    unsafe { libc::umask(mask) };
+    let listener = match bound {
+        Ok(l) => l,
+        Err(e) => { eprintln!("[ERROR] PTY host could not bind {:?}: {}", sock, e); return; }
+    };
+    let _ = std::fs::set_permissions(sock, std::fs::Permissions::from_mode(0o600));
+    if let Ok(m) = std::fs::symlink_metadata(sock) { let _ = SOCKET.set((sock.to_path_buf(), m.ino())); }
+    let sessions: HostSessions = Arc::new(Mutex::new(HashMap::new()));
+    let last_seen = Arc::new(Mutex::new(Instant::now()));
+    {
+        let (sessions, last_seen) = (sessions.clone(), last_seen.clone());
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(5));
+            if sessions.lock().unwrap().is_empty() && last_seen.lock().unwrap().elapsed() > IDLE_EXIT { exit_host(); }
+        });
+    }
+    let sys = Arc::new(NativePtySystem::default());
+    let data_dir = Arc::new(sock.parent().map(Path::to_path_buf).unwrap_or_default());
+    for stream in listener.incoming().flatten() {
+        *last_seen.lock().unwrap() = Instant::now();
+        let (sessions, sys, data_dir) = (sessions.clone(), sys.clone(), data_dir.clone());
+        std::thread::spawn(move || handle(stream, sessions, &sys, &data_dir));
+    }
+}
+
+/// Removes the socket, unless a newer host has already bound the same path, and exits.
+fn exit_host() -> ! {
+    if let Some((sock, ino)) = SOCKET.get() {
+        if std::fs::symlink_metadata(sock).is_ok_and(|m| m.ino() == *ino) { let _ = std::fs::remove_file(sock); }
+    }
+    std::process::exit(0)
+}
+
+fn handle(mut stream: UnixStream, sessions: HostSessions, sys: &NativePtySystem, data_dir: &Path) {
+    let Ok(line) = read_line_raw(&mut stream) else { return };
+    let req: HostRequest = match serde_json::from_str(&line) {
+        Ok(r) => r,
+        Err(e) => { let _ = write_message(&mut stream, &HostResponse::Error { message: e.to_string() }); return; }
+    };
+    match req {
+        HostRequest::Attach { id } => attach(stream, &sessions, &id),
+        req => {
+            let shutdown = matches!(req, HostRequest::Shutdown);
+            let res = respond(req, &sessions, sys, data_dir).unwrap_or_else(|message| HostResponse::Error { message });
+            let _ = write_message(&mut stream, &res);
+            if shutdown { exit_host(); }
+        }
+    }
+}
+
+fn respond(req: HostRequest, sessions: &HostSessions, sys: &NativePtySystem, data_dir: &Path) -> Result<HostResponse, String> {
+    let mut map = sessions.lock().unwrap();
+    let no_session = || "No session found".to_string();
+    match req {
+        HostRequest::Spawn { id, cwd, rows, cols, profile } => {
+            if map.contains_key(&id) { return Err(format!("Session {} already exists", id)); }
+            let (master, child) = create_pty(sys, rows, cols, cwd, &profile, data_dir)?;
+            let reader = master.try_clone_reader().map_err(|e| e.to_string())?;
+            let writer = Arc::new(Mutex::new(master.take_writer().map_err(|e| e.to_string())?));
+            let mirror = Arc::new(Mutex::new(Mirror { buffer: Vec::with_capacity(8192), client: None }));
+            let pid = child.process_id();
+            spawn_pump(reader, mirror.clone());
+            map.insert(id, HostSession { master, child, writer, mirror });
+            Ok(HostResponse::Spawned { pid })
+        }
+        HostRequest::Resize { id, rows, cols } => {
+            let s = map.get(&id).ok_or_else(no_session)?;
+            s.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 }).map_err(|e| e.to_string())?;
+            Ok(HostResponse::Ok)
+        }
+        HostRequest::Size { id } => {
+            let sz = map.get(&id).ok_or_else(no_session)?.master.get_size().map_err(|e| e.to_string())?;
+            Ok(HostResponse::Size { rows: sz.rows, cols: sz.cols })
+        }
+        HostRequest::ProcessGroup { id } => Ok(HostResponse::ProcessGroup { pgid: map.get(&id).ok_or_else(no_session)?.master.process_group_leader() }),
+        HostRequest::Status { id } => {
+            let s = map.get_mut(&id).ok_or_else(no_session)?;
+            Ok(match s.child.try_wait().map_err(|e| e.to_string())? {
+                Some(st) => {
+                    // The client has collected the exit status, so the session can go
+                    map.remove(&id);
+                    HostResponse::Status { exited: true, code: st.exit_code(), signal: st.signal().map(String::from) }
+                }
+                None => HostResponse::Status { exited: false, code: 0, signal: None },
+            })
+        }
+        HostRequest::Kill { id } => {
+            if let Some(mut s) = map.remove(&id) {
+                if matches!(s.child.try_wait(), Ok(None)) { let _ = s.child.kill(); }
+                if let Some(c) = s.mirror.lock().unwrap().client.take() { let _ = c.shutdown(Shutdown::Both); }
+            }
+            Ok(HostResponse::Ok)
+        }
+        HostRequest::List => Ok(HostResponse::Sessions {
+            sessions: map.iter_mut().map(|(id, s)| HostedSession { id: id.clone(), pid: s.child.process_id(), alive: matches!(s.child.try_wait(), Ok(None)) }).collect(),
+        }),
+        HostRequest::Hello { version } if version == PROTOCOL_VERSION => Ok(HostResponse::Hello { version }),
+        HostRequest::Hello { version } => Err(format!("Protocol version {} is not supported, host speaks {}", version, PROTOCOL_VERSION)),
+        HostRequest::Shutdown => {
+            for (_, mut s) in map.drain() {
+                if matches!(s.child.try_wait(), Ok(None)) { let _ = s.child.kill(); }
+                if let Some(c) = s.mirror.lock().unwrap().client.take() { let _ = c.shutdown(Shutdown::Both); }
+            }
+            Ok(HostResponse::Ok)
+        }
+        HostRequest::Attach { .. } => Err("Attach needs a connection of its own".into()),
+    }
+}
+
+/// Replays the scrollback to the new client, then forwards its input to the PTY until it disconnects.
+fn attach(mut stream: UnixStream, sessions: &HostSessions, id: &str) {
+    let found = sessions.lock().unwrap().get(id).map(|s| (s.mirror.clone(), s.writer.clone(), s.child.process_id()));
+    let Some((mirror, writer, pid)) = found else {
+        let _ = write_message(&mut stream, &HostResponse::Error { message: "No session found".into() });
+        return;
+    };
+    {
+        let mut m = mirror.lock().unwrap();
+        if write_message(&mut stream, &HostResponse::Attached { pid, replay: m.buffer.len() }).is_err() || stream.write_all(&m.buffer).is_err() { return; }
+        let Ok(out) = stream.try_clone() else { return };
+        if let Some(old) = m.client.replace(out) { let _ = old.shutdown(Shutdown::Both); }
+    }
+    let mut buf = [0u8; 4096];
+    while let Ok(n) = stream.read(&mut buf) {
+        if n == 0 { break; }
+        let mut w = writer.lock().unwrap();
+        if w.write_all(&buf[..n]).and_then(|_| w.flush()).is_err() { break; }
+    }
+}
+
+fn spawn_pump(mut reader: Box<dyn Read + Send>, mirror: Arc<Mutex<Mirror>>) {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 { break; }
+            let mut m = mirror.lock().unwrap();
+            m.buffer.extend_from_slice(&buf[..n]);
+            if m.buffer.len() > BUFFER_LIMIT { let to_rem = m.buffer.len() - BUFFER_LIMIT; m.buffer.drain(0..to_rem); }
+            if m.client.as_mut().is_some_and(|c| c.write_all(&buf[..n]).is_err()) { m.client = None; }
+        }
+        if let Some(c) = mirror.lock().unwrap().client.take() { let _ = c.shutdown(Shutdown::Both); }
+    });
+}
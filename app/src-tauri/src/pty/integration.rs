@@ -0,0 +1,114 @@
+
+use portable_pty::CommandBuilder;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const BASH_RC: &str = r#"if [ -n "$OXIDE_SHELL_LOGIN" ]; then
+  unset OXIDE_SHELL_LOGIN
+  [ -r /etc/profile ] && . /etc/profile
+  for f in ~/.bash_profile ~/.bash_login ~/.profile; do [ -r "$f" ] && { . "$f"; break; }; done
+else
+  [ -r ~/.bashrc ] && . ~/.bashrc
+fi
+__oxide_esc() { local s="${1//\\/\\\\}"; s="${s//;/\\x3b}"; printf '%s' "${s//$'\n'/\\x0a}"; }
+__oxide_precmd() { local ec=$?; __oxide_armed=; printf '\e]133;D;%s\a\e]633;P;Cwd=%s\a\e]133;A\a' "$ec" "$(__oxide_esc "$PWD")"; return $ec; }
+__oxide_preexec() {
+  [ -n "$__oxide_armed" ] && [ -z "$COMP_LINE" ] && [ "$BASH_COMMAND" != __oxide_precmd ] || return 0
+  __oxide_armed=
+  printf '\e]633;E;%s\a\e]133;C\a' "$(__oxide_esc "$BASH_COMMAND")"
+}
+PROMPT_COMMAND="__oxide_precmd${PROMPT_COMMAND:+; $PROMPT_COMMAND}"$'\n''__oxide_armed=1'
+trap '__oxide_preexec' DEBUG
+PS1="${PS1}\[\e]133;B\a\]"
+"#;
+
+const ZSH_ENV: &str = r#"__oxide_zd="$ZDOTDIR"; ZDOTDIR="${OXIDE_USER_ZDOTDIR:-$HOME}"
+[ -r "$ZDOTDIR/.zshenv" ] && . "$ZDOTDIR/.zshenv"
+ZDOTDIR="$__oxide_zd"
+"#;
+
+const ZSH_PROFILE: &str = r#"[ -r "${OXIDE_USER_ZDOTDIR:-$HOME}/.zprofile" ] && . "${OXIDE_USER_ZDOTDIR:-$HOME}/.zprofile"
+"#;
+
+const ZSH_RC: &str = r#"ZDOTDIR="${OXIDE_USER_ZDOTDIR:-$HOME}"
+[ -r "$ZDOTDIR/.zshrc" ] && . "$ZDOTDIR/.zshrc"
+autoload -Uz add-zsh-hook
+__oxide_esc() { local s="${1//\\/\\\\}"; s="${s//;/\\x3b}"; print -rn -- "${s//$'\n'/\\x0a}" }
+__oxide_precmd() { local ec=$?; print -rn -- $'\e]133;D;'"$ec"$'\a\e]633;P;Cwd='"$(__oxide_esc "$PWD")"$'\a\e]133;A\a' }
+__oxide_preexec() { print -rn -- $'\e]633;E;'"$(__oxide_esc "$1")"$'\a\e]133;C\a' }
+add-zsh-hook precmd __oxide_precmd
+add-zsh-hook preexec __oxide_preexec
+PS1="${PS1}%{"$'\e]133;B\a'"%}"
+"#;
+
+const FISH_INIT: &str = r#"function __oxide_esc; string replace -a '\\' '\\\\' -- $argv | string replace -a ';' '\\x3b' | string join '\\x0a'; end
+function __oxide_prompt --on-event fish_prompt; printf '\e]633;P;Cwd=%s\a\e]133;A\a' (__oxide_esc $PWD); end
+function __oxide_preexec --on-event fish_preexec; printf '\e]633;E;%s\a\e]133;C\a' (__oxide_esc $argv); end
+function __oxide_postexec --on-event fish_postexec; printf '\e]133;D;%s\a' $status; end
+"#;
+
+fn script_dir(data_dir: &Path) -> PathBuf { data_dir.join("shell_integration") }
+
+/// Creates `dir` as 0700, refusing one that already exists with another owner or looser mode,
+/// since every spawned shell sources what is inside.
+#[cfg(unix)]
+fn private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    let meta = fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.uid() != unsafe { libc::geteuid() } || meta.mode() & 0o077 != 0 {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("{:?} is not a private directory", dir)));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn private_dir(dir: &Path) -> io::Result<()> { fs::create_dir_all(dir) }
+
+/// Writes each script to a temp sibling and renames it into place.
+fn write_scripts(dir: &Path, files: &[(&str, &str)]) -> bool {
+    let write = |name: &str, content: &str| -> io::Result<()> {
+        let tmp = dir.join(format!(".{}.{}.tmp", name.trim_start_matches('.'), std::process::id()));
+        let mut opts = fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+        let _ = fs::remove_file(&tmp);
+        let res = opts.open(&tmp).and_then(|mut f| f.write_all(content.as_bytes())).and_then(|_| fs::rename(&tmp, dir.join(name)));
+        if res.is_err() { let _ = fs::remove_file(&tmp); }
+        res
+    };
+    let ok = private_dir(dir).and_then(|_| files.iter().try_for_each(|(n, c)| write(n, c)));
+    if let Err(e) = &ok { eprintln!("[WARNING] Shell integration disabled: {}", e); }
+    ok.is_ok()
+}
+
+/// Injects OSC 133/633 prompt markers for bash, zsh and fish.
+/// Returns true when the login flag has already been handled by the injected startup files.
+pub fn inject(cmd: &mut CommandBuilder, program: &str, login: bool, data_dir: &Path) -> bool {
+    let dir = script_dir(data_dir);
+    let name = Path::new(program).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    match name.as_str() {
+        "bash" if write_scripts(&dir, &[("bashrc", BASH_RC)]) => {
+            cmd.arg("--init-file"); cmd.arg(dir.join("bashrc"));
+            if login { cmd.env("OXIDE_SHELL_LOGIN", "1"); }
+            login
+        }
+        "zsh" => {
+            let zd = dir.join("zsh");
+            if private_dir(&dir).is_err() || !write_scripts(&zd, &[(".zshenv", ZSH_ENV), (".zprofile", ZSH_PROFILE), (".zshrc", ZSH_RC)]) { return false; }
+            if let Ok(user) = std::env::var("ZDOTDIR") { cmd.env("OXIDE_USER_ZDOTDIR", user); }
+            cmd.env("ZDOTDIR", zd);
+            false
+        }
+        "fish" if write_scripts(&dir, &[("init.fish", FISH_INIT)]) => {
+            cmd.arg("--init-command"); cmd.arg(format!("source '{}'", dir.join("init.fish").to_string_lossy()));
+            false
+        }
+        _ => false,
+    }
+}
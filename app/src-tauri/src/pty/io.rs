@@ -1,20 +1,23 @@
 
+use crate::pty::broadcast::{deliver, Delivery};
+use crate::pty::markers::CommandRecord;
 use crate::state::AppState;
 use std::io::Write;
+use std::time::Duration;
 
 #[tauri::command]
 pub fn write_to_pty(state: tauri::State<'_, AppState>, id: String, data: String) -> Result<(), String> {
-    let mut map = state.sessions.lock().unwrap();
-    let s = map.get_mut(&id).ok_or("No session found")?;
-    s.writer.write_all(data.as_bytes()).map_err(|e| e.to_string())?;
-    s.writer.flush().map_err(|e| e.to_string())
+    let writer = state.sessions.lock().unwrap().get(&id).ok_or("No session found")?.writer.clone();
+    let mut w = writer.lock().unwrap();
+    w.write_all(data.as_bytes()).map_err(|e| e.to_string())?;
+    w.flush().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn write_to_all_ptys(state: tauri::State<'_, AppState>, ids: Vec<String>, data: String) -> Result<(), String> {
-    let mut map = state.sessions.lock().unwrap();
-    for id in ids { if let Some(s) = map.get_mut(&id) { let _ = s.writer.write_all(data.as_bytes()); let _ = s.writer.flush(); } }
-    Ok(())
+pub async fn write_to_all_ptys(state: tauri::State<'_, AppState>, ids: Vec<String>, data: String, wait_idle_ms: Option<u64>) -> Result<Vec<Delivery>, String> {
+    let sessions = state.sessions.clone();
+    tauri::async_runtime::spawn_blocking(move || deliver(&sessions, ids, &data, wait_idle_ms.map(Duration::from_millis)))
+        .await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -35,3 +38,14 @@ pub fn clear_pty_buffer(state: tauri::State<'_, AppState>, id: String) -> Result
     data.clear();
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_pty_commands(state: tauri::State<'_, AppState>, id: String, limit: Option<usize>) -> Result<Vec<CommandRecord>, String> {
+    let sh_arc = {
+        let map = state.sessions.lock().unwrap();
+        map.get(&id).ok_or("No session found")?.shell.clone()
+    };
+    let sh = sh_arc.lock().unwrap();
+    let skip = sh.history.len().saturating_sub(limit.unwrap_or(usize::MAX));
+    Ok(sh.history.iter().skip(skip).chain(sh.current.iter()).cloned().collect())
+}
@@ -1,4 +1,5 @@
 
+use crate::pty::models::PtySessionInfo;
 use crate::state::AppState;
 use portable_pty::PtySize;
 
@@ -6,18 +7,19 @@ use portable_pty::PtySize;
 pub fn resize_pty(state: tauri::State<'_, AppState>, id: String, rows: u16, cols: u16) -> Result<(), String> {
     if let Some(s) = state.sessions.lock().unwrap().get(&id) {
         let _ = s.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 });
+        if let Some(rec) = s.recorder.lock().unwrap().as_mut() { rec.resize(cols, rows); }
     }
     Ok(())
 }
 
 #[tauri::command]
-pub fn close_pty(state: tauri::State<'_, AppState>, id: String) -> Result<(), String> {
-    if let Some(mut s) = state.sessions.lock().unwrap().remove(&id) { let _ = s.child.kill(); }
+pub fn set_pty_visibility(state: tauri::State<'_, AppState>, id: String, visible: bool) -> Result<(), String> {
+    if let Some(s) = state.sessions.lock().unwrap().get(&id) { *s.is_visible.lock().unwrap() = visible; }
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_pty_visibility(state: tauri::State<'_, AppState>, id: String, visible: bool) -> Result<(), String> {
-    if let Some(s) = state.sessions.lock().unwrap().get(&id) { *s.is_visible.lock().unwrap() = visible; }
-    Ok(())
+pub fn list_pty_sessions(state: tauri::State<'_, AppState>) -> Result<Vec<PtySessionInfo>, String> {
+    let map = state.sessions.lock().unwrap();
+    Ok(map.iter().map(|(id, s)| PtySessionInfo { id: id.clone(), pid: s.child.process_id(), cwd: s.shell.lock().unwrap().cwd.clone() }).collect())
 }
@@ -0,0 +1,137 @@
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+const MAX_OSC_LEN: usize = 8192;
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CommandRecord {
+    pub command: Option<String>,
+    pub cwd: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShellEvent {
+    PromptStarted,
+    CommandText { command: String },
+    CommandStarted { command: Option<String>, cwd: Option<String>, started_at: i64 },
+    CommandFinished { command: Option<String>, exit_code: Option<i32>, duration_ms: i64 },
+    CwdChanged { cwd: String },
+}
+
+/// Extracts OSC payloads from the raw PTY stream, keeping partial sequences across reads.
+#[derive(Default)]
+pub struct OscScanner {
+    buf: Vec<u8>,
+    in_osc: bool,
+    esc: bool,
+}
+
+impl OscScanner {
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut out = Vec::new();
+        for &b in bytes {
+            if !self.in_osc {
+                if self.esc && b == b']' { self.in_osc = true; self.buf.clear(); }
+                self.esc = b == 0x1b;
+                continue;
+            }
+            match b {
+                0x07 => { out.push(String::from_utf8_lossy(&self.buf).to_string()); self.in_osc = false; }
+                b'\\' if self.esc => { out.push(String::from_utf8_lossy(&self.buf).to_string()); self.in_osc = false; }
+                0x1b => {}
+                _ if self.esc => { self.in_osc = false; }
+                _ => { self.buf.push(b); if self.buf.len() > MAX_OSC_LEN { self.in_osc = false; } }
+            }
+            self.esc = b == 0x1b;
+        }
+        out
+    }
+}
+
+/// Prompt/command state of a session, driven by OSC 133/633 and OSC 7 markers.
+#[derive(Default)]
+pub struct ShellTracker {
+    pub integrated: bool,
+    pub idle: bool,
+    pub cwd: Option<String>,
+    pub current: Option<CommandRecord>,
+    pub history: VecDeque<CommandRecord>,
+    /// Number of prompts seen so far, so callers can wait for the next one.
+    pub prompts: u64,
+    pending_text: Option<String>,
+}
+
+impl ShellTracker {
+    pub fn apply(&mut self, payload: &str) -> Vec<ShellEvent> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut parts = payload.splitn(3, ';');
+        let (code, kind, rest) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""), parts.next());
+        match (code, kind) {
+            ("133" | "633", "A") => { self.integrated = true; self.idle = true; self.prompts += 1; vec![ShellEvent::PromptStarted] }
+            ("133" | "633", "B") => { self.integrated = true; self.idle = true; vec![] }
+            ("133" | "633", "C") => {
+                self.integrated = true; self.idle = false;
+                let rec = CommandRecord { command: self.pending_text.take(), cwd: self.cwd.clone(), started_at: now, finished_at: None, duration_ms: None, exit_code: None };
+                let ev = ShellEvent::CommandStarted { command: rec.command.clone(), cwd: rec.cwd.clone(), started_at: now };
+                self.current = Some(rec);
+                vec![ev]
+            }
+            ("133" | "633", "D") => {
+                let Some(mut rec) = self.current.take() else { return vec![] };
+                rec.finished_at = Some(now); rec.duration_ms = Some(now - rec.started_at);
+                rec.exit_code = rest.and_then(|c| c.split(';').next()).and_then(|c| c.trim().parse().ok());
+                let ev = ShellEvent::CommandFinished { command: rec.command.clone(), exit_code: rec.exit_code, duration_ms: now - rec.started_at };
+                self.history.push_back(rec);
+                if self.history.len() > MAX_HISTORY { self.history.pop_front(); }
+                vec![ev]
+            }
+            ("633", "E") => {
+                let cmd = unescape(rest.and_then(|r| r.split(';').next()).unwrap_or(""));
+                if let Some(rec) = self.current.as_mut().filter(|r| r.command.is_none()) { rec.command = Some(cmd.clone()); } else { self.pending_text = Some(cmd.clone()); }
+                vec![ShellEvent::CommandText { command: cmd }]
+            }
+            ("633", "P") => match rest.and_then(|r| r.strip_prefix("Cwd=")) { Some(c) => self.set_cwd(unescape(c)), None => vec![] },
+            ("7", _) => {
+                let url = payload.get(2..).unwrap_or("").trim_start_matches("file://");
+                match url.find('/') { Some(i) => self.set_cwd(percent_decode(&url[i..])), None => vec![] }
+            }
+            _ => vec![],
+        }
+    }
+
+    fn set_cwd(&mut self, cwd: String) -> Vec<ShellEvent> {
+        if cwd.is_empty() || self.cwd.as_deref() == Some(cwd.as_str()) { return vec![]; }
+        self.cwd = Some(cwd.clone());
+        vec![ShellEvent::CwdChanged { cwd }]
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let (b, mut out, mut i) = (s.as_bytes(), Vec::with_capacity(s.len()), 0);
+    while i < b.len() {
+        if b[i] == b'\\' && b.get(i + 1) == Some(&b'\\') { out.push(b'\\'); i += 2; continue; }
+        if b[i] == b'\\' && b.get(i + 1) == Some(&b'x') {
+            if let Some(v) = s.get(i + 2..i + 4).and_then(|h| u8::from_str_radix(h, 16).ok()) { out.push(v); i += 4; continue; }
+        }
+        out.push(b[i]); i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn percent_decode(s: &str) -> String {
+    let (b, mut out, mut i) = (s.as_bytes(), Vec::with_capacity(s.len()), 0);
+    while i < b.len() {
+        if b[i] == b'%' {
+            if let Some(v) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) { out.push(v); i += 3; continue; }
+        }
+        out.push(b[i]); i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
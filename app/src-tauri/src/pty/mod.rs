@@ -4,9 +4,29 @@ pub mod spawner;
 pub mod reader;
 pub mod io;
 pub mod management;
+pub mod profiles;
+pub mod integration;
+pub mod markers;
+pub mod host;
+pub mod scrollback;
+pub mod recording;
+pub mod procinfo;
+pub mod signals;
+pub mod stream;
+pub mod broadcast;
+pub mod expect;
 
-pub use models::PtySession;
+pub use models::{PtySession, PtySessionInfo, ShellProfile};
 pub use spawner::create_pty;
 pub use reader::spawn_reader;
-pub use io::{write_to_pty, write_to_all_ptys, get_pty_buffer};
-pub use management::{resize_pty, close_pty, set_pty_visibility};
+pub use markers::{CommandRecord, ShellEvent, ShellTracker};
+pub use io::{write_to_pty, write_to_all_ptys, get_pty_buffer, get_pty_commands};
+pub use management::{resize_pty, set_pty_visibility, list_pty_sessions};
+pub use signals::{close_pty, signal_pty};
+pub use broadcast::{BroadcastGroup, Delivery, broadcast_to_group, create_broadcast_group, list_broadcast_groups};
+pub use expect::{ExpectMatch, pty_expect, pty_run};
+pub use stream::{attach_pty_stream, detach_pty_stream, ack_pty_data};
+pub use scrollback::{search_pty_scrollback, Spool};
+pub use recording::{start_pty_recording, stop_pty_recording, replay_pty_recording};
+pub use procinfo::pty_process_info;
+pub use profiles::{list_shell_profiles, save_shell_profile, delete_shell_profile};
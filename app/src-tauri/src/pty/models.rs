@@ -1,12 +1,52 @@
 
+use crate::pty::markers::ShellTracker;
+use crate::pty::recording::Recorder;
+use crate::pty::scrollback::Spool;
+use crate::pty::stream::OutputStream;
 use portable_pty::{Child, MasterPty};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::io::Write;
 
 pub struct PtySession {
-    pub writer: Box<dyn Write + Send>,
+    pub writer: Arc<Mutex<Box<dyn Write + Send>>>,
     pub master: Box<dyn MasterPty + Send>,
     pub child: Box<dyn Child + Send>,
     pub is_visible: Arc<Mutex<bool>>,
     pub buffer: Arc<Mutex<Vec<u8>>>,
+    pub shell: Arc<Mutex<ShellTracker>>,
+    pub spool: Arc<Mutex<Spool>>,
+    pub recorder: Arc<Mutex<Option<Recorder>>>,
+    pub output: Arc<OutputStream>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PtySessionInfo {
+    pub id: String,
+    pub pid: Option<u32>,
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShellProfile {
+    pub id: String,
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub login: bool,
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+impl ShellProfile {
+    pub fn platform_default() -> Self {
+        #[cfg(target_os = "windows")] let (program, args) = ("cmd.exe".to_string(), vec![]);
+        #[cfg(not(target_os = "windows"))] let (program, args) = (std::env::var("SHELL").unwrap_or_else(|_| "bash".into()), vec!["-i".to_string()]);
+        Self { id: "default".into(), name: "Default".into(), program, args, env: HashMap::new(), login: false, prompt: None }
+    }
 }
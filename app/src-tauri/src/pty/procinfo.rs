@@ -0,0 +1,86 @@
+
+use crate::state::AppState;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub ppid: u32,
+    pub pgid: i32,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PtyProcessInfo {
+    pub pid: u32,
+    pub foreground_pgid: Option<i32>,
+    /// True when the shell itself owns the terminal, i.e. nothing is running in the foreground.
+    pub shell_in_foreground: bool,
+    pub foreground_command: Option<Vec<String>>,
+    pub cwd: Option<String>,
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+    pub processes: Vec<ProcessEntry>,
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{ProcessEntry, PtyProcessInfo};
+    use std::collections::HashMap;
+    use std::fs;
+
+    struct Stat { ppid: u32, pgid: i32, tpgid: i32, name: String, ticks: u64, rss_pages: u64 }
+
+    fn read_stat(pid: u32) -> Option<Stat> {
+        let s = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let (open, close) = (s.find('(')?, s.rfind(')')?);
+        let f: Vec<&str> = s[close + 1..].split_whitespace().collect();
+        let num = |i: usize| f.get(i).and_then(|v| v.parse::<i64>().ok());
+        Some(Stat {
+            name: s[open + 1..close].to_string(), ppid: num(1)? as u32, pgid: num(2)? as i32, tpgid: num(5)? as i32,
+            ticks: (num(11)? + num(12)?) as u64, rss_pages: num(21)?.max(0) as u64,
+        })
+    }
+
+    fn cmdline(pid: u32) -> Vec<String> {
+        fs::read(format!("/proc/{}/cmdline", pid)).map(|b| b.split(|&c| c == 0).filter(|a| !a.is_empty()).map(|a| String::from_utf8_lossy(a).to_string()).collect()).unwrap_or_default()
+    }
+
+    fn cwd(pid: u32) -> Option<String> { fs::read_link(format!("/proc/{}/cwd", pid)).ok().map(|p| p.to_string_lossy().to_string()) }
+
+    pub fn inspect(root: u32) -> Result<PtyProcessInfo, String> {
+        let root_stat = read_stat(root).ok_or("Process not found")?;
+        let (tck, page) = unsafe { (libc::sysconf(libc::_SC_CLK_TCK).max(1) as f64, libc::sysconf(libc::_SC_PAGESIZE).max(1) as u64) };
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut stats = HashMap::new();
+        for e in fs::read_dir("/proc").map_err(|e| e.to_string())?.flatten() {
+            let Some(pid) = e.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else { continue };
+            if let Some(st) = read_stat(pid) { children.entry(st.ppid).or_default().push(pid); stats.insert(pid, st); }
+        }
+        let (mut stack, mut processes) = (vec![root], Vec::new());
+        while let Some(pid) = stack.pop() {
+            let Some(st) = stats.get(&pid) else { continue };
+            processes.push(ProcessEntry { pid, ppid: st.ppid, pgid: st.pgid, name: st.name.clone(), cmdline: cmdline(pid), cpu_seconds: st.ticks as f64 / tck, rss_bytes: st.rss_pages * page });
+            stack.extend(children.get(&pid).into_iter().flatten());
+        }
+        let fg = (root_stat.tpgid > 0).then_some(root_stat.tpgid);
+        let leader = fg.and_then(|g| processes.iter().find(|p| p.pid as i32 == g).or_else(|| processes.iter().find(|p| p.pgid == g)));
+        Ok(PtyProcessInfo {
+            pid: root, foreground_pgid: fg, shell_in_foreground: fg == Some(root_stat.pgid),
+            foreground_command: leader.map(|p| p.cmdline.clone()),
+            cwd: leader.and_then(|p| cwd(p.pid)).or_else(|| cwd(root)),
+            cpu_seconds: processes.iter().map(|p| p.cpu_seconds).sum(), rss_bytes: processes.iter().map(|p| p.rss_bytes).sum(),
+            processes,
+        })
+    }
+}
+
+#[tauri::command]
+pub fn pty_process_info(state: tauri::State<'_, AppState>, id: String) -> Result<PtyProcessInfo, String> {
+    let pid = state.sessions.lock().unwrap().get(&id).ok_or("No session found")?.child.process_id().ok_or("Session has no process")?;
+    #[cfg(target_os = "linux")] { linux::inspect(pid) }
+    #[cfg(not(target_os = "linux"))] { let _ = pid; Err("Process inspection is only available on Linux".into()) }
+}
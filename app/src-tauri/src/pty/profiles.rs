@@ -0,0 +1,50 @@
+
+use crate::pty::models::ShellProfile;
+use crate::state::AppState;
+use rusqlite::{params, Connection, OptionalExtension};
+
+fn row_to_profile(r: &rusqlite::Row) -> rusqlite::Result<ShellProfile> {
+    let (args, env): (String, String) = (r.get(3)?, r.get(4)?);
+    Ok(ShellProfile {
+        id: r.get(0)?, name: r.get(1)?, program: r.get(2)?,
+        args: serde_json::from_str(&args).unwrap_or_default(),
+        env: serde_json::from_str(&env).unwrap_or_default(),
+        login: r.get::<_, i32>(5)? == 1, prompt: r.get(6)?,
+    })
+}
+
+pub fn load_profile(conn: &Connection, id: &str) -> Result<ShellProfile, String> {
+    let stored = conn.query_row("SELECT id, name, program, args, env, login, prompt FROM shell_profiles WHERE id = ?", params![id], row_to_profile)
+        .optional().map_err(|e| e.to_string())?;
+    match stored {
+        Some(p) => Ok(p),
+        None if id == "default" => Ok(ShellProfile::platform_default()),
+        None => Err(format!("Unknown shell profile: {}", id)),
+    }
+}
+
+#[tauri::command]
+pub fn list_shell_profiles(state: tauri::State<'_, AppState>) -> Result<Vec<ShellProfile>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut st = conn.prepare("SELECT id, name, program, args, env, login, prompt FROM shell_profiles ORDER BY name").map_err(|e| e.to_string())?;
+    let mut res = st.query_map([], row_to_profile).map_err(|e| e.to_string())?.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())?;
+    if !res.iter().any(|p| p.id == "default") { res.insert(0, ShellProfile::platform_default()); }
+    Ok(res)
+}
+
+#[tauri::command]
+pub fn save_shell_profile(state: tauri::State<'_, AppState>, profile: ShellProfile) -> Result<(), String> {
+    if profile.id.is_empty() || profile.program.is_empty() { return Err("Profile id and program are required".into()); }
+    let (args, env) = (serde_json::to_string(&profile.args).map_err(|e| e.to_string())?, serde_json::to_string(&profile.env).map_err(|e| e.to_string())?);
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute("INSERT OR REPLACE INTO shell_profiles (id, name, program, args, env, login, prompt) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![profile.id, profile.name, profile.program, args, env, if profile.login { 1 } else { 0 }, profile.prompt]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_shell_profile(state: tauri::State<'_, AppState>, id: String) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM shell_profiles WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -1,21 +1,35 @@
 
+use crate::pty::markers::OscScanner;
+use crate::pty::models::PtySession;
+use crate::pty::signals::reap_session;
 use std::io::Read;
-use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter};
 
-pub fn spawn_reader(app: AppHandle, id: String, mut reader: Box<dyn Read + Send>, is_visible: Arc<Mutex<bool>>, buffer: Arc<Mutex<Vec<u8>>>) {
+pub const BUFFER_LIMIT: usize = 512 * 1024;
+
+pub fn spawn_reader(app: AppHandle, id: String, mut reader: Box<dyn Read + Send>, session: &PtySession) {
+    let (is_visible, buffer, shell, spool, recorder, output) = (session.is_visible.clone(), session.buffer.clone(), session.shell.clone(), session.spool.clone(), session.recorder.clone(), session.output.clone());
+    output.spawn_flusher(app.clone(), id.clone(), is_visible.clone());
     std::thread::spawn(move || {
         let mut buf = [0u8; 8192];
+        let mut osc = OscScanner::default();
         while let Ok(n) = reader.read(&mut buf) {
             if n == 0 { break; }
             let bytes = &buf[..n];
-            {
-                let mut b = buffer.lock().unwrap(); b.extend_from_slice(bytes);
-                if b.len() > 512 * 1024 { let to_rem = b.len() - (512 * 1024); b.drain(0..to_rem); }
-            }
-            if *is_visible.lock().unwrap() { let _ = app.emit(&format!("pty-data-{}", id), &String::from_utf8_lossy(bytes)); }
-            let s = if bytes.ends_with(b"$ ") || bytes.ends_with(b"# ") || bytes.ends_with(b"> ") || bytes.ends_with(b"% ") { "idle" } else { "working" };
-            let _ = app.emit(&format!("pty-status-{}", id), s);
+            output.write(bytes, &buffer, *is_visible.lock().unwrap());
+            spool.lock().unwrap().push(bytes);
+            if let Some(rec) = recorder.lock().unwrap().as_mut() { rec.output(bytes); }
+            let (events, integrated, idle) = {
+                let mut sh = shell.lock().unwrap();
+                let ev: Vec<_> = osc.feed(bytes).iter().flat_map(|p| sh.apply(p)).collect();
+                (ev, sh.integrated, sh.idle)
+            };
+            for ev in events { let _ = app.emit(&format!("pty-shell-{}", id), ev); }
+            // Fall back to the prompt-suffix guess for shells without OSC 133 support
+            let idle = if integrated { idle } else { bytes.ends_with(b"$ ") || bytes.ends_with(b"# ") || bytes.ends_with(b"> ") || bytes.ends_with(b"% ") };
+            let _ = app.emit(&format!("pty-status-{}", id), if idle { "idle" } else { "working" });
         }
+        output.close();
+        reap_session(&app, &id, &buffer);
     });
 }
@@ -0,0 +1,165 @@
+
+use crate::pty::stream::Utf8Decoder;
+use crate::state::AppState;
+use portable_pty::{Child, ChildKiller, ExitStatus, MasterPty, PtySize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAX_IDLE: f64 = 2.0;
+
+/// Writes an asciicast v2 file: a JSON header line followed by `[time, code, data]` events.
+pub struct Recorder {
+    pub path: PathBuf,
+    out: BufWriter<File>,
+    started: Instant,
+    decoder: Utf8Decoder,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, cols: u16, rows: u16) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() { std::fs::create_dir_all(dir)?; }
+        let mut out = BufWriter::new(File::create(path)?);
+        let header = serde_json::json!({ "version": 2, "width": cols, "height": rows, "timestamp": chrono::Utc::now().timestamp(), "env": { "TERM": "xterm-256color" } });
+        writeln!(out, "{}", header)?;
+        Ok(Self { path: path.to_path_buf(), out, started: Instant::now(), decoder: Utf8Decoder::default() })
+    }
+
+    fn event(&mut self, code: &str, data: &str) {
+        let _ = writeln!(self.out, "{}", serde_json::json!([self.started.elapsed().as_secs_f64(), code, data]));
+    }
+
+    pub fn output(&mut self, bytes: &[u8]) {
+        let data = self.decoder.decode(bytes);
+        if !data.is_empty() { self.event("o", &data); }
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) { self.event("r", &format!("{}x{}", cols, rows)); }
+
+    pub fn finish(mut self) -> std::io::Result<PathBuf> {
+        self.out.flush()?;
+        Ok(self.path)
+    }
+}
+
+/// Feeds a playback thread's output into the regular reader, like a PTY master would.
+struct ReplayReader {
+    rx: Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl Read for ReplayReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() { match self.rx.recv() { Ok(d) => self.pending = d, Err(_) => return Ok(0) } }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+struct ReplayMaster {
+    size: PtySize,
+    reader: Mutex<Option<ReplayReader>>,
+}
+
+impl MasterPty for ReplayMaster {
+    fn resize(&self, _size: PtySize) -> anyhow::Result<()> { Ok(()) }
+
+    fn get_size(&self) -> anyhow::Result<PtySize> { Ok(self.size) }
+
+    fn try_clone_reader(&self) -> anyhow::Result<Box<dyn Read + Send>> {
+        let r = self.reader.lock().unwrap().take().ok_or_else(|| anyhow::anyhow!("Replay reader already taken"))?;
+        Ok(Box::new(r))
+    }
+
+    fn take_writer(&self) -> anyhow::Result<Box<dyn Write + Send>> { Ok(Box::new(std::io::sink())) }
+
+    #[cfg(unix)]
+    fn process_group_leader(&self) -> Option<i32> { None }
+
+    #[cfg(unix)]
+    fn as_raw_fd(&self) -> Option<std::os::unix::io::RawFd> { None }
+
+    #[cfg(unix)]
+    fn tty_name(&self) -> Option<PathBuf> { None }
+}
+
+#[derive(Debug, Clone)]
+struct ReplayChild {
+    stop: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+impl ChildKiller for ReplayChild {
+    fn kill(&mut self) -> std::io::Result<()> { self.stop.store(true, Ordering::SeqCst); Ok(()) }
+
+    fn clone_killer(&self) -> Box<dyn ChildKiller + Send + Sync> { Box::new(self.clone()) }
+}
+
+impl Child for ReplayChild {
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> { Ok(self.done.load(Ordering::SeqCst).then(|| ExitStatus::with_exit_code(0))) }
+
+    fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        while !self.done.load(Ordering::SeqCst) { std::thread::sleep(Duration::from_millis(50)); }
+        Ok(ExitStatus::with_exit_code(0))
+    }
+
+    fn process_id(&self) -> Option<u32> { None }
+
+    #[cfg(windows)]
+    fn as_raw_handle(&self) -> Option<std::os::windows::io::RawHandle> { None }
+}
+
+#[tauri::command]
+pub fn start_pty_recording(state: tauri::State<'_, AppState>, id: String, path: Option<String>) -> Result<String, String> {
+    let map = state.sessions.lock().unwrap();
+    let s = map.get(&id).ok_or("No session found")?;
+    let size = s.master.get_size().map_err(|e| e.to_string())?;
+    let path = path.map(PathBuf::from).unwrap_or_else(|| state.data_dir.join("recordings").join(format!("{}-{}.cast", id, chrono::Local::now().format("%Y%m%d-%H%M%S"))));
+    let rec = Recorder::create(&path, size.cols, size.rows).map_err(|e| e.to_string())?;
+    *s.recorder.lock().unwrap() = Some(rec);
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn stop_pty_recording(state: tauri::State<'_, AppState>, id: String) -> Result<Option<String>, String> {
+    let rec = state.sessions.lock().unwrap().get(&id).ok_or("No session found")?.recorder.lock().unwrap().take();
+    match rec { Some(r) => r.finish().map(|p| Some(p.to_string_lossy().to_string())).map_err(|e| e.to_string()), None => Ok(None) }
+}
+
+/// Plays a recording into a read-only session that emits the usual `pty-data-{id}` events.
+#[tauri::command]
+pub fn replay_pty_recording(app: AppHandle, id: String, path: String, speed: Option<f64>) -> Result<bool, String> {
+    if app.state::<AppState>().sessions.lock().unwrap().contains_key(&id) { return Ok(false); }
+    let mut lines = BufReader::new(File::open(&path).map_err(|e| e.to_string())?).lines();
+    let header: serde_json::Value = serde_json::from_str(&lines.next().ok_or("Empty recording")?.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+    if header["version"].as_u64() != Some(2) { return Err("Only asciicast v2 recordings are supported".into()); }
+    let size = PtySize { cols: header["width"].as_u64().unwrap_or(80) as u16, rows: header["height"].as_u64().unwrap_or(24) as u16, pixel_width: 0, pixel_height: 0 };
+    let speed = speed.filter(|s| *s > 0.0).unwrap_or(1.0);
+    let (tx, rx) = channel();
+    let (stop, done) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+    let master = ReplayMaster { size, reader: Mutex::new(Some(ReplayReader { rx, pending: Vec::new() })) };
+    let child = ReplayChild { stop: stop.clone(), done: done.clone() };
+    crate::setup::register_session(app.clone(), id.clone(), Box::new(master), Box::new(child), Vec::new(), true)?;
+    std::thread::spawn(move || {
+        let mut last = 0.0;
+        for line in lines.map_while(Result::ok) {
+            if stop.load(Ordering::SeqCst) { break; }
+            let Ok(ev) = serde_json::from_str::<(f64, String, String)>(&line) else { continue };
+            std::thread::sleep(Duration::from_secs_f64(((ev.0 - last).clamp(0.0, MAX_IDLE)) / speed));
+            last = ev.0;
+            if let ("r", Some((c, r))) = (ev.1.as_str(), ev.2.split_once('x')) {
+                let _ = app.emit(&format!("pty-resize-{}", id), (c.parse::<u16>().unwrap_or(0), r.parse::<u16>().unwrap_or(0)));
+            }
+            if ev.1 == "o" && tx.send(ev.2.into_bytes()).is_err() { break; }
+        }
+        done.store(true, Ordering::SeqCst);
+    });
+    Ok(true)
+}
@@ -0,0 +1,182 @@
+
+use crate::db::{get_setting, set_setting};
+use crate::state::AppState;
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use regex::RegexBuilder;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+const SEGMENT_SIZE: usize = 64 * 1024;
+const DEFAULT_LIMIT: u64 = 16 * 1024 * 1024;
+const LIMIT_KEY: &str = "scrollback_spool_limit";
+const MAX_HITS: usize = 1000;
+/// Bytes of spooled output used to find where a host replay continues it.
+const REPLAY_OVERLAP: usize = 4096;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScrollbackHit {
+    pub line: usize,
+    pub offset: usize,
+    pub length: usize,
+    pub text: String,
+}
+
+/// Full session output, gzip-compressed in 64 KiB members. The live file rotates into `.old.gz`
+/// once it reaches half the limit, so at most `limit` bytes stay on disk per session.
+pub struct Spool {
+    path: PathBuf,
+    old: PathBuf,
+    pending: Vec<u8>,
+    limit: u64,
+}
+
+impl Spool {
+    pub fn open(dir: &Path, id: &str, limit: u64) -> Self {
+        let name: String = id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+        let _ = fs::create_dir_all(dir);
+        Self { path: dir.join(format!("{}.log.gz", name)), old: dir.join(format!("{}.old.gz", name)), pending: Vec::with_capacity(SEGMENT_SIZE), limit }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        if self.limit == 0 { return; }
+        self.pending.extend_from_slice(bytes);
+        if self.pending.len() >= SEGMENT_SIZE { let _ = self.flush_segment(); }
+    }
+
+    /// Writes out buffered output; called on drop and when the app exits.
+    pub fn flush(&mut self) {
+        if self.limit > 0 && !self.pending.is_empty() { let _ = self.flush_segment(); }
+    }
+
+    /// Appends the part of a host replay that is not spooled yet: everything after the last place
+    /// the spool's final bytes occur in it, or all of it when they don't occur (output was lost
+    /// from the replay window while the app was closed).
+    pub fn append_replay(&mut self, replay: &[u8]) {
+        if self.limit == 0 || replay.is_empty() { return; }
+        let (mut tail, mut r, mut buf) = (Vec::new(), self.reader(), [0u8; 8192]);
+        while let Ok(n) = r.read(&mut buf) {
+            if n == 0 { break; }
+            tail.extend_from_slice(&buf[..n]);
+            if tail.len() > 2 * REPLAY_OVERLAP { tail.drain(..tail.len() - REPLAY_OVERLAP); }
+        }
+        let tail = &tail[tail.len().saturating_sub(REPLAY_OVERLAP)..];
+        let pat: String = std::iter::once("(?-u)".to_string()).chain(tail.iter().map(|b| format!("\\x{:02x}", b))).collect();
+        let start = match regex::bytes::Regex::new(&pat) {
+            Ok(re) if !tail.is_empty() => re.find_iter(replay).last().map_or(0, |m| m.end()),
+            _ => 0,
+        };
+        self.push(&replay[start..]);
+    }
+
+    fn flush_segment(&mut self) -> std::io::Result<()> {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::fast());
+        enc.write_all(&self.pending)?;
+        OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(&enc.finish()?)?;
+        self.pending.clear();
+        if fs::metadata(&self.path)?.len() > self.limit / 2 { fs::rename(&self.path, &self.old)?; }
+        Ok(())
+    }
+
+    /// Decompressed stream over everything spooled so far, oldest first. File reads stop at the
+    /// lengths seen here, so flushes and rotations after the spool lock is released don't leak in.
+    pub fn reader(&self) -> Box<dyn Read + Send> {
+        let gz = |p: &Path| -> Box<dyn Read + Send> {
+            match File::open(p).and_then(|f| Ok((f.metadata()?.len(), f))) {
+                Ok((len, f)) => Box::new(MultiGzDecoder::new(f.take(len))),
+                Err(_) => Box::new(std::io::empty()),
+            }
+        };
+        Box::new(gz(&self.old).chain(gz(&self.path)).chain(Cursor::new(self.pending.clone())))
+    }
+
+    pub fn is_enabled(&self) -> bool { self.limit > 0 }
+
+    pub fn files(&self) -> [PathBuf; 2] { [self.path.clone(), self.old.clone()] }
+
+    pub fn remove(&mut self) {
+        self.pending.clear();
+        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_file(&self.old);
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) { self.flush(); }
+}
+
+pub fn spool_dir(data_dir: &Path) -> PathBuf { data_dir.join("scrollback") }
+
+pub fn spool_limit(conn: &rusqlite::Connection) -> u64 {
+    get_setting(conn, LIMIT_KEY).and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_LIMIT)
+}
+
+/// Drops spool files left behind by sessions that did not survive a restart.
+pub fn prune_spools(dir: &Path, keep: &[PathBuf]) {
+    for e in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if !keep.contains(&e.path()) { let _ = fs::remove_file(e.path()); }
+    }
+}
+
+/// Feeds `r` through a small ANSI stripper and calls `f` for each plain-text line until it returns false.
+pub fn for_each_plain_line(mut r: impl Read, mut f: impl FnMut(usize, &str) -> bool) {
+    #[derive(PartialEq)]
+    enum St { Text, Esc, Csi, Str, StrEsc }
+    let (mut st, mut line, mut no, mut buf) = (St::Text, Vec::new(), 1, [0u8; 8192]);
+    while let Ok(n) = r.read(&mut buf) {
+        if n == 0 { break; }
+        for &b in &buf[..n] {
+            st = match st {
+                St::Text => match b {
+                    0x1b => St::Esc,
+                    b'\n' => {
+                        if !f(no, &String::from_utf8_lossy(&line)) { return; }
+                        line.clear(); no += 1; St::Text
+                    }
+                    b'\t' => { line.push(b); St::Text }
+                    0..=0x1f | 0x7f => St::Text,
+                    _ => { line.push(b); St::Text }
+                },
+                St::Esc => match b { b'[' => St::Csi, b']' | b'P' | b'X' | b'^' | b'_' => St::Str, _ => St::Text },
+                St::Csi => if (0x40..=0x7e).contains(&b) { St::Text } else { St::Csi },
+                St::Str => match b { 0x07 => St::Text, 0x1b => St::StrEsc, _ => St::Str },
+                St::StrEsc => St::Text,
+            };
+        }
+    }
+    if !line.is_empty() { f(no, &String::from_utf8_lossy(&line)); }
+}
+
+#[tauri::command]
+pub async fn search_pty_scrollback(state: tauri::State<'_, AppState>, id: String, query: String, regex: Option<bool>) -> Result<Vec<ScrollbackHit>, String> {
+    let src: Box<dyn Read + Send> = {
+        let map = state.sessions.lock().unwrap();
+        let s = map.get(&id).ok_or("No session found")?;
+        let spool = s.spool.lock().unwrap();
+        if spool.is_enabled() { spool.reader() } else { Box::new(Cursor::new(s.buffer.lock().unwrap().clone())) }
+    };
+    let pat = if regex.unwrap_or(false) { query } else { regex::escape(&query) };
+    let re = RegexBuilder::new(&pat).build().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut hits = Vec::new();
+        for_each_plain_line(src, |no, text| {
+            for m in re.find_iter(text) {
+                hits.push(ScrollbackHit { line: no, offset: text[..m.start()].chars().count(), length: m.as_str().chars().count(), text: text.to_string() });
+            }
+            hits.len() < MAX_HITS
+        });
+        hits.truncate(MAX_HITS);
+        hits
+    }).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_scrollback_limit(state: tauri::State<'_, AppState>) -> Result<u64, String> {
+    Ok(spool_limit(&state.db.lock().unwrap()))
+}
+
+#[tauri::command]
+pub fn set_scrollback_limit(state: tauri::State<'_, AppState>, bytes: u64) -> Result<(), String> {
+    set_setting(&state.db.lock().unwrap(), LIMIT_KEY, &bytes.to_string())
+}
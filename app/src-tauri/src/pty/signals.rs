@@ -0,0 +1,126 @@
+
+use crate::pty::models::PtySession;
+use crate::state::AppState;
+use portable_pty::{Child, ExitStatus};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const DEFAULT_GRACE_MS: u64 = 2000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PtyExit {
+    pub code: Option<u32>,
+    pub signal: Option<String>,
+}
+
+impl From<&ExitStatus> for PtyExit {
+    fn from(st: &ExitStatus) -> Self {
+        match st.signal() { Some(sig) => Self { code: None, signal: Some(sig.to_string()) }, None => Self { code: Some(st.exit_code()), signal: None } }
+    }
+}
+
+#[cfg(unix)]
+fn parse_signal(name: &str) -> Result<i32, String> {
+    match name.trim().to_uppercase().trim_start_matches("SIG") {
+        "INT" => Ok(libc::SIGINT),
+        "TERM" => Ok(libc::SIGTERM),
+        "HUP" => Ok(libc::SIGHUP),
+        "KILL" => Ok(libc::SIGKILL),
+        other => Err(format!("Unsupported signal: {}", other)),
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(target: i32, sig: i32) -> Result<(), String> {
+    if unsafe { libc::kill(target, sig) } == 0 { Ok(()) } else { Err(std::io::Error::last_os_error().to_string()) }
+}
+
+/// Flushes per-session side files once a session is gone for good.
+fn finalize(s: &PtySession) {
+    s.spool.lock().unwrap().remove();
+    if let Some(rec) = s.recorder.lock().unwrap().take() { let _ = rec.finish(); }
+}
+
+fn emit_exit(app: &AppHandle, id: &str, exit: PtyExit) {
+    let _ = app.emit(&format!("pty-exit-{}", id), exit);
+}
+
+/// Called by the reader once the PTY hits EOF: reports how the child ended and drops the session,
+/// unless it was already closed or replaced by a new session with the same id.
+pub fn reap_session(app: &AppHandle, id: &str, buffer: &Arc<std::sync::Mutex<Vec<u8>>>) {
+    let state = app.state::<AppState>();
+    let s = {
+        let mut map = state.sessions.lock().unwrap();
+        if !map.get(id).is_some_and(|s| Arc::ptr_eq(&s.buffer, buffer)) { return; }
+        map.remove(id)
+    };
+    let Some(mut s) = s else { return };
+    let exit = wait_for_exit(s.child.as_mut(), Duration::from_millis(DEFAULT_GRACE_MS)).map(|st| PtyExit::from(&st)).unwrap_or(PtyExit { code: None, signal: None });
+    finalize(&s);
+    emit_exit(app, id, exit);
+}
+
+fn wait_for_exit(child: &mut (dyn Child + Send), timeout: Duration) -> Option<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(st)) => return Some(st),
+            Err(_) => return None,
+            Ok(None) if Instant::now() >= deadline => return None,
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// Sends SIGHUP, waits up to `grace`, then escalates to SIGKILL and reports the final status,
+/// or an unknown exit (no code, no signal) when the child could not be reaped.
+fn staged_close(app: AppHandle, id: String, mut s: PtySession, grace: Duration) {
+    std::thread::spawn(move || {
+        #[cfg(unix)]
+        let pid = s.child.process_id().map(|p| p as i32);
+        #[cfg(unix)]
+        if let Some(pid) = pid { let _ = send_signal(pid, libc::SIGHUP); } else { let _ = s.child.kill(); }
+        #[cfg(not(unix))]
+        let _ = s.child.kill();
+        let status = wait_for_exit(s.child.as_mut(), grace);
+        #[cfg(unix)]
+        let status = match (status, pid) {
+            (None, Some(pid)) => { let _ = send_signal(pid, libc::SIGKILL); wait_for_exit(s.child.as_mut(), Duration::from_millis(DEFAULT_GRACE_MS)) }
+            (st, _) => st,
+        };
+        finalize(&s);
+        emit_exit(&app, &id, status.map(|st| PtyExit::from(&st)).unwrap_or(PtyExit { code: None, signal: None }));
+    });
+}
+
+#[tauri::command]
+pub fn close_pty(app: AppHandle, id: String, grace_ms: Option<u64>) -> Result<(), String> {
+    let s = app.state::<AppState>().sessions.lock().unwrap().remove(&id);
+    if let Some(s) = s { staged_close(app.clone(), id, s, Duration::from_millis(grace_ms.unwrap_or(DEFAULT_GRACE_MS))); }
+    Ok(())
+}
+
+/// Delivers a signal to the session's foreground job (like a terminal would), or to the shell itself.
+#[tauri::command]
+pub fn signal_pty(state: tauri::State<'_, AppState>, id: String, signal: String, foreground: Option<bool>) -> Result<(), String> {
+    let map = state.sessions.lock().unwrap();
+    let s = map.get(&id).ok_or("No session found")?;
+    #[cfg(unix)]
+    {
+        let sig = parse_signal(&signal)?;
+        let pid = s.child.process_id().map(|p| p as i32);
+        let pgid = if foreground.unwrap_or(true) { s.master.process_group_leader().filter(|g| *g > 0) } else { None };
+        match (pgid, pid) {
+            (Some(g), _) => send_signal(-g, sig),
+            (None, Some(p)) => send_signal(p, sig),
+            (None, None) => Err("Session has no process".into()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (s, signal, foreground);
+        Err("Signals are only supported on Unix".into())
+    }
+}
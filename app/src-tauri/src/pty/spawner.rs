@@ -1,16 +1,31 @@
 
+use crate::pty::integration::inject;
+use crate::pty::models::ShellProfile;
 use portable_pty::{CommandBuilder, PtySize, PtySystem, MasterPty, Child};
 use std::path::Path;
 
-pub fn create_pty(sys: &dyn PtySystem, r: u16, c: u16, cwd: Option<String>) -> Result<(Box<dyn MasterPty + Send>, Box<dyn Child + Send>), String> {
+/// Shells that take `-l` to start a login shell; other programs get no extra argument.
+#[cfg(not(target_os = "windows"))]
+const LOGIN_SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "ksh", "mksh", "yash"];
+
+#[cfg(not(target_os = "windows"))]
+fn is_login_shell(program: &str) -> bool {
+    Path::new(program).file_stem().is_some_and(|s| LOGIN_SHELLS.contains(&s.to_string_lossy().as_ref()))
+}
+
+pub fn create_pty(sys: &dyn PtySystem, r: u16, c: u16, cwd: Option<String>, profile: &ShellProfile, data_dir: &Path) -> Result<(Box<dyn MasterPty + Send>, Box<dyn Child + Send>), String> {
     let pair = sys.openpty(PtySize { rows: r, cols: c, pixel_width: 0, pixel_height: 0 }).map_err(|e| e.to_string())?;
-    #[cfg(target_os = "windows")] let mut cmd = CommandBuilder::new("cmd.exe");
-    #[cfg(not(target_os = "windows"))] let mut cmd = CommandBuilder::new("bash");
-    #[cfg(not(target_os = "windows"))] { 
-        cmd.arg("-i"); 
-        cmd.env("TERM", "xterm-256color"); 
-        cmd.env("PS1", "> ");
+    let mut cmd = CommandBuilder::new(&profile.program);
+    #[cfg(not(target_os = "windows"))] {
+        let login_handled = inject(&mut cmd, &profile.program, profile.login, data_dir);
+        if profile.login && !login_handled && is_login_shell(&profile.program) { cmd.arg("-l"); }
+        cmd.env("TERM", "xterm-256color");
     }
+    #[cfg(target_os = "windows")]
+    let _ = data_dir;
+    cmd.args(&profile.args);
+    for (k, v) in &profile.env { cmd.env(k, v); }
+    if let Some(ps1) = &profile.prompt { cmd.env("PS1", ps1); }
     if let Some(p) = cwd { if Path::new(&p).exists() { cmd.cwd(p); } }
     let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
     Ok((pair.master, child))
@@ -0,0 +1,211 @@
+
+use crate::pty::reader::BUFFER_LIMIT;
+use crate::state::AppState;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter};
+
+const FRAME_SIZE: usize = 64 * 1024;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(8);
+/// Bytes a channel consumer may have un-acked before we stop sending.
+const WINDOW: usize = 1024 * 1024;
+/// Queued bytes at which the PTY reader blocks, pushing backpressure onto the child process.
+const MAX_PENDING: usize = 4 * 1024 * 1024;
+/// A consumer that has not acked for this long is considered gone and gets detached, which is
+/// announced as `pty-stream-dropped-{id}` so it can re-attach and get the scrollback again.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Incremental UTF-8 decoder that never splits a multi-byte character across chunks.
+#[derive(Default)]
+pub struct Utf8Decoder {
+    partial: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.partial.extend_from_slice(bytes);
+        let keep = incomplete_tail(&self.partial);
+        let text = String::from_utf8_lossy(&self.partial[..self.partial.len() - keep]).to_string();
+        self.partial.drain(..self.partial.len() - keep);
+        text
+    }
+}
+
+fn incomplete_tail(b: &[u8]) -> usize {
+    for back in 1..=b.len().min(4) {
+        let c = b[b.len() - back];
+        if c & 0xC0 == 0x80 { continue; }
+        let need = if c >= 0xF0 { 4 } else if c >= 0xE0 { 3 } else if c >= 0xC0 { 2 } else { 1 };
+        return if need > back { back } else { 0 };
+    }
+    0
+}
+
+struct OutState {
+    pending: Vec<u8>,
+    first_at: Option<Instant>,
+    in_flight: usize,
+    channel: Option<Channel>,
+    last_ack: Instant,
+    closed: bool,
+    written: u64,
+    /// A stalled consumer was dropped and the flusher has not announced it yet.
+    dropped: bool,
+}
+
+enum Frame {
+    Data(Vec<u8>, Option<Channel>),
+    Dropped,
+}
+
+impl OutState {
+    fn drop_consumer(&mut self) { self.channel = None; self.in_flight = 0; self.pending.clear(); self.first_at = None; }
+
+    fn drop_stalled(&mut self) { self.drop_consumer(); self.dropped = true; }
+
+    fn stalled(&self) -> bool { self.last_ack.elapsed() > STALL_TIMEOUT }
+}
+
+/// Coalesces PTY output into frames and delivers them over a binary channel with ack-based
+/// flow control, or as `pty-data-{id}` string events when no channel is attached.
+pub struct OutputStream {
+    state: Mutex<OutState>,
+    cv: Condvar,
+}
+
+impl Default for OutputStream {
+    fn default() -> Self {
+        Self { state: Mutex::new(OutState { pending: Vec::new(), first_at: None, in_flight: 0, channel: None, last_ack: Instant::now(), closed: false, written: 0, dropped: false }), cv: Condvar::new() }
+    }
+}
+
+impl OutputStream {
+    fn lock(&self) -> MutexGuard<'_, OutState> { self.state.lock().unwrap() }
+
+    /// Appends to the scrollback and queues the bytes for the live consumer, blocking while a
+    /// channel consumer is too far behind.
+    pub fn write(&self, bytes: &[u8], buffer: &Mutex<Vec<u8>>, live: bool) {
+        let mut st = self.lock();
+        while st.channel.is_some() && st.pending.len() >= MAX_PENDING {
+            if st.stalled() { st.drop_stalled(); self.cv.notify_all(); break; }
+            st = self.cv.wait_timeout(st, Duration::from_millis(100)).unwrap().0;
+        }
+        {
+            let mut b = buffer.lock().unwrap(); b.extend_from_slice(bytes);
+            if b.len() > BUFFER_LIMIT { let to_rem = b.len() - BUFFER_LIMIT; b.drain(0..to_rem); }
+        }
+        st.written += bytes.len() as u64;
+        if live || st.channel.is_some() {
+            st.pending.extend_from_slice(bytes);
+            st.first_at.get_or_insert_with(Instant::now);
+            self.cv.notify_all();
+        }
+    }
+
+    /// Sends the current scrollback as the first frame, then streams live output from there on.
+    pub fn attach(&self, ch: Channel, buffer: &Mutex<Vec<u8>>) -> Result<(), String> {
+        let mut st = self.lock();
+        let snapshot = buffer.lock().unwrap().clone();
+        st.drop_consumer();
+        st.dropped = false;
+        st.in_flight = snapshot.len();
+        ch.send(InvokeResponseBody::Raw(snapshot)).map_err(|e| e.to_string())?;
+        st.last_ack = Instant::now();
+        st.channel = Some(ch);
+        self.cv.notify_all();
+        Ok(())
+    }
+
+    /// Total bytes ever written, a stable offset that survives buffer truncation.
+    pub fn written(&self) -> u64 { self.lock().written }
+
+    /// Output written since `offset` that is still held in the buffer, and whether the stream has closed.
+    pub fn since(&self, offset: u64, buffer: &Mutex<Vec<u8>>) -> (Vec<u8>, bool) {
+        let st = self.lock();
+        let b = buffer.lock().unwrap();
+        let n = (st.written.saturating_sub(offset) as usize).min(b.len());
+        (b[b.len() - n..].to_vec(), st.closed)
+    }
+
+    pub fn detach(&self) { self.lock().drop_consumer(); self.cv.notify_all(); }
+
+    pub fn ack(&self, bytes: usize) {
+        let mut st = self.lock();
+        st.in_flight = st.in_flight.saturating_sub(bytes);
+        st.last_ack = Instant::now();
+        self.cv.notify_all();
+    }
+
+    /// Marks the stream finished and gives the flusher a moment to deliver what is left.
+    pub fn close(&self) {
+        let mut st = self.lock();
+        st.closed = true;
+        self.cv.notify_all();
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while !st.pending.is_empty() && Instant::now() < deadline { st = self.cv.wait_timeout(st, Duration::from_millis(20)).unwrap().0; }
+    }
+
+    pub fn spawn_flusher(self: &Arc<Self>, app: AppHandle, id: String, is_visible: Arc<Mutex<bool>>) {
+        let out = self.clone();
+        std::thread::spawn(move || {
+            let mut decoder = Utf8Decoder::default();
+            while let Some(frame) = out.next_frame() {
+                match frame {
+                    Frame::Dropped => { let _ = app.emit(&format!("pty-stream-dropped-{}", id), out.written()); }
+                    Frame::Data(frame, Some(ch)) => if ch.send(InvokeResponseBody::Raw(frame)).is_err() { out.detach(); },
+                    Frame::Data(frame, None) => {
+                        let text = decoder.decode(&frame);
+                        if !text.is_empty() && *is_visible.lock().unwrap() { let _ = app.emit(&format!("pty-data-{}", id), text); }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Waits until a frame is due (full, old enough, or closing) and the consumer has window left.
+    fn next_frame(&self) -> Option<Frame> {
+        let mut st = self.lock();
+        loop {
+            if std::mem::take(&mut st.dropped) { return Some(Frame::Dropped); }
+            if st.pending.is_empty() {
+                if st.closed { return None; }
+                st = self.cv.wait(st).unwrap();
+                continue;
+            }
+            let age = st.first_at.map(|t| t.elapsed()).unwrap_or(FLUSH_INTERVAL);
+            let due = st.closed || st.pending.len() >= FRAME_SIZE || age >= FLUSH_INTERVAL;
+            let blocked = st.channel.is_some() && st.in_flight >= WINDOW;
+            if blocked && st.stalled() { st.drop_stalled(); self.cv.notify_all(); continue; }
+            if due && !blocked { break; }
+            let wait = if blocked { Duration::from_millis(100) } else { FLUSH_INTERVAL - age };
+            st = self.cv.wait_timeout(st, wait).unwrap().0;
+        }
+        let n = st.pending.len().min(FRAME_SIZE);
+        let frame: Vec<u8> = st.pending.drain(..n).collect();
+        st.first_at = if st.pending.is_empty() { None } else { Some(Instant::now()) };
+        if st.channel.is_some() { st.in_flight += n; }
+        let channel = st.channel.clone();
+        self.cv.notify_all();
+        Some(Frame::Data(frame, channel))
+    }
+}
+
+#[tauri::command]
+pub fn attach_pty_stream(state: tauri::State<'_, AppState>, id: String, on_data: Channel) -> Result<(), String> {
+    let map = state.sessions.lock().unwrap();
+    let s = map.get(&id).ok_or("No session found")?;
+    s.output.attach(on_data, &s.buffer)
+}
+
+#[tauri::command]
+pub fn detach_pty_stream(state: tauri::State<'_, AppState>, id: String) -> Result<(), String> {
+    if let Some(s) = state.sessions.lock().unwrap().get(&id) { s.output.detach(); }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn ack_pty_data(state: tauri::State<'_, AppState>, id: String, bytes: usize) -> Result<(), String> {
+    if let Some(s) = state.sessions.lock().unwrap().get(&id) { s.output.ack(bytes); }
+    Ok(())
+}
@@ -1,84 +1,154 @@
 
+use crate::search::engine::{search_files, SearchProgress, SearchStats};
+use crate::search::matcher::Matcher;
 use crate::search::models::SearchResult;
-use ignore::WalkBuilder;
-use std::fs;
+use crate::search::saved::record_history;
+use crate::state::{AppState, IndexMap};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use serde::Deserialize;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
 
-#[derive(Debug, Deserialize)]
+pub(crate) const MAX_RESULTS: usize = 1000;
+const BATCH_SIZE: usize = 200;
+const TICK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchOptions {
     pub case_sensitive: bool,
     pub use_regex: bool,
     pub whole_word: bool,
+    /// Case-insensitive unless the query contains an uppercase letter.
+    #[serde(default)]
+    pub smart_case: bool,
+    #[serde(default)]
+    pub context_before: usize,
+    #[serde(default)]
+    pub context_after: usize,
+    /// Lets regex matches span lines; `^`/`$` still anchor at line boundaries.
+    #[serde(default)]
+    pub multiline: bool,
+    /// Globs relative to each search root; a leading `!` is not needed for `exclude`.
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Named file types as known to ripgrep (`rust`, `ts`, `py`, ...).
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    #[serde(default)]
+    pub exclude_types: Vec<String>,
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// Searches files excluded by .gitignore, .ignore and global git excludes.
+    #[serde(default)]
+    pub no_ignore: bool,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+}
+
+impl SearchOptions {
+    /// Whether the walk visits the same files the content index covers.
+    pub fn index_compatible(&self) -> bool { !self.include_hidden && !self.no_ignore && !self.follow_symlinks }
+
+    pub fn has_filters(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty() || !self.file_types.is_empty() || !self.exclude_types.is_empty() || self.max_file_size.is_some() || self.max_depth.is_some()
+    }
+}
+
+/// Runs a search to completion, keeping at most about `MAX_RESULTS` hits.
+pub(crate) fn collect_results(paths: &[String], options: &SearchOptions, matcher: &Matcher, indexes: &IndexMap) -> Result<Vec<SearchResult>, String> {
+    collect_up_to(paths, options, matcher, indexes, MAX_RESULTS).map(|(results, _)| results)
+}
+
+/// Runs a search until more than `cap` hits are found. The flag tells whether it stopped early,
+/// in which case the hits kept depend on walk order.
+pub(crate) fn collect_up_to(paths: &[String], options: &SearchOptions, matcher: &Matcher, indexes: &IndexMap, cap: usize) -> Result<(Vec<SearchResult>, bool), String> {
+    let results = Mutex::new(Vec::new());
+    search_files(paths, options, matcher, indexes, &AtomicBool::new(false), &SearchStats::default(), &|hits| {
+        let mut global_results = results.lock().unwrap();
+        global_results.extend(hits);
+        global_results.len() <= cap
+    })?;
+    let results = results.into_inner().unwrap();
+    let truncated = results.len() > cap;
+    Ok((results, truncated))
 }
 
 #[tauri::command]
 pub async fn search_in_projects(
+    state: tauri::State<'_, AppState>,
     paths: Vec<String>, 
     query: String, 
     options: SearchOptions
 ) -> Result<Vec<SearchResult>, String> {
     if query.len() < 2 { return Ok(vec![]); }
 
-    let query_arc = Arc::new(if options.case_sensitive { query } else { query.to_lowercase() });
-    let results = Arc::new(Mutex::new(Vec::new()));
-
-    tauri::async_runtime::spawn_blocking::<_, Result<Vec<SearchResult>, String>>(move || {
-        for root in paths {
-            let results_clone = Arc::clone(&results);
-            let query_clone = Arc::clone(&query_arc);
-            
-            let walker = WalkBuilder::new(&root)
-                .hidden(true)
-                .git_ignore(true)
-                .threads(num_cpus::get())
-                .build_parallel();
+    let matcher = Matcher::new(&query, &options)?;
+    let indexes = state.indexes.lock().unwrap().clone();
+    record_history(&state.db, &query, &options, &paths);
 
-            walker.run(|| {
-                let results = Arc::clone(&results_clone);
-                let query = Arc::clone(&query_clone);
-                
-                Box::new(move |entry| {
-                    let entry = match entry {
-                        Ok(e) => e,
-                        Err(_) => return ignore::WalkState::Continue,
-                    };
-
-                    if entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-                        if let Ok(content) = fs::read_to_string(entry.path()) {
-                            let mut local_results = Vec::new();
-                            for (idx, line) in content.lines().enumerate() {
-                                let match_line = if options.case_sensitive { 
-                                    line.to_string() 
-                                } else { 
-                                    line.to_lowercase() 
-                                };
-
-                                if match_line.contains(&*query) {
-                                    local_results.push(SearchResult {
-                                        path: entry.path().to_string_lossy().to_string(),
-                                        line: idx + 1,
-                                        content: line.trim().to_string(),
-                                    });
-                                }
-                                if local_results.len() > 100 { break; }
-                            }
+    tauri::async_runtime::spawn_blocking(move || collect_results(&paths, &options, &matcher, &indexes)).await.map_err(|e| e.to_string())?
+}
 
-                            if !local_results.is_empty() {
-                                let mut global_results = results.lock().unwrap();
-                                global_results.extend(local_results);
-                                if global_results.len() > 1000 {
-                                    return ignore::WalkState::Quit;
-                                }
-                            }
-                        }
-                    }
-                    ignore::WalkState::Continue
-                })
+/// Streams hits as `search-results-{id}` batches and counters as `search-progress-{id}` until the walk
+/// finishes, `max_results` (default `MAX_RESULTS`) is reached or `cancel_search` is called. Resolves with
+/// the final progress; queries shorter than two characters finish at once with nothing found.
+#[tauri::command]
+pub async fn start_search(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: String,
+    paths: Vec<String>,
+    query: String,
+    options: SearchOptions,
+    max_results: Option<usize>,
+) -> Result<SearchProgress, String> {
+    if query.len() < 2 { return Ok(SearchProgress { done: true, ..Default::default() }); }
+    let max_results = max_results.unwrap_or(MAX_RESULTS);
+    let matcher = Matcher::new(&query, &options)?;
+    record_history(&state.db, &query, &options, &paths);
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut searches = state.searches.lock().unwrap();
+        if searches.contains_key(&id) { return Err(format!("Search already running: {}", id)); }
+        searches.insert(id.clone(), cancel.clone());
+    }
+    let (searches, id_c, indexes) = (state.searches.clone(), id.clone(), state.indexes.lock().unwrap().clone());
+    let res = tauri::async_runtime::spawn_blocking(move || {
+        let (stats, batch, finished) = (SearchStats::default(), Mutex::new(Vec::new()), AtomicBool::new(false));
+        let flush = |batch: &mut Vec<SearchResult>| if !batch.is_empty() { let _ = app.emit(&format!("search-results-{}", id), std::mem::take(batch)); };
+        let walked = std::thread::scope(|s| {
+            s.spawn(|| while !finished.load(Ordering::Relaxed) {
+                std::thread::sleep(TICK);
+                flush(&mut batch.lock().unwrap());
+                let _ = app.emit(&format!("search-progress-{}", id), stats.snapshot());
             });
-        }
-        
-        let final_results = results.lock().unwrap().clone();
-        Ok(final_results)
-    }).await.map_err(|e| e.to_string())?
+            let walked = search_files(&paths, &options, &matcher, &indexes, &cancel, &stats, &|hits| {
+                let mut b = batch.lock().unwrap();
+                b.extend(hits);
+                if b.len() >= BATCH_SIZE { flush(&mut b); }
+                (stats.snapshot().results as usize) < max_results
+            });
+            finished.store(true, Ordering::Relaxed);
+            walked
+        });
+        flush(&mut batch.lock().unwrap());
+        let progress = SearchProgress { done: true, cancelled: cancel.load(Ordering::Relaxed), ..stats.snapshot() };
+        let _ = app.emit(&format!("search-progress-{}", id), &progress);
+        walked.map(|_| progress)
+    }).await.map_err(|e| e.to_string());
+    searches.lock().unwrap().remove(&id_c);
+    res?
+}
+
+#[tauri::command]
+pub fn cancel_search(state: tauri::State<'_, AppState>, id: String) -> Result<bool, String> {
+    let searches = state.searches.lock().unwrap();
+    Ok(searches.get(&id).map(|c| c.store(true, Ordering::Relaxed)).is_some())
 }
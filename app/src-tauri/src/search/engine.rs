@@ -0,0 +1,175 @@
+
+use crate::fs::encoding::decode_text;
+use crate::search::matcher::Matcher;
+use crate::search::models::{MatchRange, SearchResult};
+use crate::state::IndexMap;
+use crate::search::commands::SearchOptions;
+use ignore::overrides::OverrideBuilder;
+use ignore::types::TypesBuilder;
+use ignore::{WalkBuilder, WalkParallel, WalkState};
+use serde::Serialize;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+const MAX_PER_FILE: usize = 100;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchProgress {
+    pub files_scanned: u64,
+    pub files_matched: u64,
+    pub bytes_read: u64,
+    pub results: u64,
+    pub binary_files: u64,
+    pub done: bool,
+    pub cancelled: bool,
+}
+
+/// Counters shared by the walker threads.
+#[derive(Default)]
+pub struct SearchStats {
+    files_scanned: AtomicU64,
+    files_matched: AtomicU64,
+    bytes_read: AtomicU64,
+    results: AtomicU64,
+    binary_files: AtomicU64,
+}
+
+impl SearchStats {
+    pub fn snapshot(&self) -> SearchProgress {
+        SearchProgress {
+            files_scanned: self.files_scanned.load(Ordering::Relaxed), files_matched: self.files_matched.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed), results: self.results.load(Ordering::Relaxed),
+            binary_files: self.binary_files.load(Ordering::Relaxed), done: false, cancelled: false,
+        }
+    }
+}
+
+/// Byte offset where each line starts, and each line without its terminator.
+fn split_lines(text: &str) -> (Vec<usize>, Vec<&str>) {
+    let mut starts = vec![0];
+    starts.extend(text.match_indices('\n').map(|(i, _)| i + 1).filter(|&i| i < text.len()));
+    let lines = starts.iter().enumerate().map(|(k, &s)| {
+        let e = starts.get(k + 1).copied().unwrap_or(text.len());
+        text[s..e].trim_end_matches(['\r', '\n'])
+    }).collect();
+    (starts, lines)
+}
+
+/// Searches a file's raw bytes. Binary files yield at most one `binary` entry; other files are
+/// decoded first so UTF-16 and Latin-1 sources are searched too.
+pub fn search_content(path: &Path, bytes: &[u8], m: &Matcher) -> Vec<SearchResult> {
+    let path_s = path.to_string_lossy().to_string();
+    let Some(doc) = decode_text(bytes) else {
+        let text = String::from_utf8_lossy(bytes);
+        let Some(&(s, _)) = m.spans(&text).first() else { return vec![] };
+        let line = text[..s].matches('\n').count() + 1;
+        return vec![SearchResult { path: path_s, line, end_line: line, encoding: "binary".into(), binary: true, ..Default::default() }];
+    };
+    let text = doc.text.as_str();
+    if text.is_empty() { return vec![]; }
+    let (starts, lines) = split_lines(text);
+    // (first line, last line, ranges within the displayed content)
+    let mut hits: Vec<(usize, usize, Vec<MatchRange>)> = Vec::new();
+    if m.multiline {
+        let line_of = |off: usize| starts.partition_point(|&x| x <= off) - 1;
+        for (s, e) in m.spans(text) {
+            let (sl, el) = (line_of(s), line_of(e - 1));
+            if hits.last().is_none_or(|h| sl > h.1) {
+                if hits.len() > MAX_PER_FILE { break; }
+                hits.push((sl, el, Vec::new()));
+            }
+            let h = hits.last_mut().unwrap();
+            h.1 = h.1.max(el);
+            let col = |b: usize| text[starts[h.0]..b].chars().count();
+            h.2.push(MatchRange { start: col(s), end: col(e) });
+        }
+        // Single-line hits are displayed trimmed, so shift their columns past the indentation.
+        for (sl, _, ranges) in hits.iter_mut().filter(|h| h.0 == h.1) {
+            let line = lines[*sl];
+            let lead = line[..line.len() - line.trim_start().len()].chars().count();
+            let len = line.trim().chars().count();
+            for r in ranges.iter_mut() { (r.start, r.end) = (r.start.saturating_sub(lead).min(len), r.end.saturating_sub(lead).min(len)); }
+        }
+    } else {
+        for (idx, line) in lines.iter().enumerate() {
+            if m.is_match(line) { hits.push((idx, idx, m.ranges(line))); }
+            if hits.len() > MAX_PER_FILE { break; }
+        }
+    }
+    let (cb, ca) = m.context;
+    hits.into_iter().map(|(sl, el, matches)| SearchResult {
+        path: path_s.clone(), line: sl + 1, end_line: el + 1,
+        content: if sl == el { lines[sl].trim().to_string() } else { text[starts[sl]..starts[el] + lines[el].len()].to_string() },
+        matches,
+        before: lines[sl.saturating_sub(cb)..sl].iter().map(|l| l.to_string()).collect(),
+        after: lines[el + 1..(el + 1 + ca).min(lines.len())].iter().map(|l| l.to_string()).collect(),
+        encoding: doc.encoding.name().to_string(),
+        binary: false,
+    }).collect()
+}
+
+/// Parallel walker over `root` honoring the scope filters of `o`.
+pub fn build_walker(root: &str, o: &SearchOptions) -> Result<WalkParallel, String> {
+    let mut b = WalkBuilder::new(root);
+    b.hidden(!o.include_hidden).parents(!o.no_ignore).ignore(!o.no_ignore).git_ignore(!o.no_ignore).git_exclude(!o.no_ignore).git_global(!o.no_ignore)
+        .follow_links(o.follow_symlinks).max_depth(o.max_depth).max_filesize(o.max_file_size).threads(num_cpus::get());
+    if !o.include.is_empty() || !o.exclude.is_empty() {
+        let mut ob = OverrideBuilder::new(root);
+        for g in &o.include { ob.add(g).map_err(|e| e.to_string())?; }
+        for g in &o.exclude { ob.add(&format!("!{}", g.trim_start_matches('!'))).map_err(|e| e.to_string())?; }
+        b.overrides(ob.build().map_err(|e| e.to_string())?);
+    }
+    if !o.file_types.is_empty() || !o.exclude_types.is_empty() {
+        let mut tb = TypesBuilder::new();
+        tb.add_defaults();
+        for t in &o.file_types { tb.select(t); }
+        for t in &o.exclude_types { tb.negate(t); }
+        b.types(tb.build().map_err(|e| e.to_string())?);
+    }
+    Ok(b.build_parallel())
+}
+
+/// Walks `paths` in parallel and hands each file's hits to `on_file`, which returns false to stop the walk.
+/// Setting `cancel` from another thread stops it as well. Invalid globs or file types fail before any walking.
+/// Roots with a ready content index only read the files the index cannot rule out.
+pub fn search_files(paths: &[String], opts: &SearchOptions, matcher: &Matcher, indexes: &IndexMap, cancel: &AtomicBool, stats: &SearchStats, on_file: &(dyn Fn(Vec<SearchResult>) -> bool + Sync)) -> Result<(), String> {
+    let walkers = paths.iter().map(|p| build_walker(p, opts)).collect::<Result<Vec<_>, _>>()?;
+    let quit = AtomicBool::new(false);
+    let stopped = || cancel.load(Ordering::Relaxed) || quit.load(Ordering::Relaxed);
+    let visit = |path: &Path| {
+        stats.files_scanned.fetch_add(1, Ordering::Relaxed);
+        let Ok(content) = fs::read(path) else { return };
+        stats.bytes_read.fetch_add(content.len() as u64, Ordering::Relaxed);
+        let hits = search_content(path, &content, matcher);
+        if hits.is_empty() { return; }
+        if hits[0].binary { stats.binary_files.fetch_add(1, Ordering::Relaxed); }
+        stats.files_matched.fetch_add(1, Ordering::Relaxed);
+        stats.results.fetch_add(hits.len() as u64, Ordering::Relaxed);
+        if !on_file(hits) { quit.store(true, Ordering::Relaxed); }
+    };
+    for (root, walker) in paths.iter().zip(walkers) {
+        if stopped() { break; }
+        let candidates = matcher.trigrams.as_ref().filter(|_| opts.index_compatible())
+            .and_then(|t| indexes.get(root)?.candidates(t));
+        if let Some(c) = candidates.as_ref().filter(|_| !opts.has_filters()) {
+            let _ = c.par_iter().try_for_each(|p| if stopped() { Err(()) } else { visit(Path::new(p)); Ok(()) });
+            continue;
+        }
+        let candidates: Option<HashSet<String>> = candidates.map(|c| c.into_iter().collect());
+        let (candidates, stopped, visit) = (&candidates, &stopped, &visit);
+        walker.run(|| {
+            Box::new(move |entry| {
+                if stopped() { return WalkState::Quit; }
+                let Ok(entry) = entry else { return WalkState::Continue };
+                if !entry.file_type().map(|ft| ft.is_file()).unwrap_or(false) { return WalkState::Continue; }
+                if candidates.as_ref().is_some_and(|c| !c.contains(entry.path().to_string_lossy().as_ref())) { return WalkState::Continue; }
+                visit(entry.path());
+                WalkState::Continue
+            })
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+
+use crate::index::trigram::{required_trigrams, Trigram};
+use crate::search::commands::SearchOptions;
+use crate::search::models::MatchRange;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Compiled form of a query plus its `SearchOptions`, shared by every search entry point.
+pub struct Matcher {
+    re: Regex,
+    expand: bool,
+    pub multiline: bool,
+    pub context: (usize, usize),
+    /// Trigrams a matching file must contain, used to narrow candidates through the content index.
+    pub trigrams: Option<Vec<Trigram>>,
+}
+
+impl Matcher {
+    pub fn new(query: &str, opts: &SearchOptions) -> Result<Self, String> {
+        let pat = if opts.use_regex { query.to_string() } else { regex::escape(query) };
+        let pat = if opts.whole_word { format!(r"\b(?:{})\b", pat) } else { pat };
+        let sensitive = opts.case_sensitive || (opts.smart_case && has_uppercase(query, opts.use_regex));
+        let multiline = opts.multiline && opts.use_regex;
+        let re = RegexBuilder::new(&pat).case_insensitive(!sensitive).multi_line(multiline).build().map_err(|e| e.to_string())?;
+        Ok(Self { re, expand: opts.use_regex, multiline, context: (opts.context_before, opts.context_after),
+            trigrams: required_trigrams(query, opts.use_regex, sensitive) })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool { self.re.is_match(text) }
+
+    pub fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        self.re.find_iter(text).filter(|m| m.end() > m.start()).map(|m| (m.start(), m.end())).collect()
+    }
+
+    /// Replaces every match, expanding `$1`/`${name}` capture references only in regex mode.
+    pub fn replace(&self, text: &str, replacement: &str) -> (String, usize) {
+        let count = self.re.find_iter(text).count();
+        if count == 0 { return (text.to_string(), 0); }
+        let out = if self.expand { self.re.replace_all(text, replacement) } else { self.re.replace_all(text, regex::NoExpand(replacement)) };
+        (out.into_owned(), count)
+    }
+
+    /// Byte range of every match in `text` with its expanded replacement, as `replace` would substitute.
+    pub fn replacements(&self, text: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        self.re.captures_iter(text).map(|c| {
+            let mut out = String::new();
+            if self.expand { c.expand(replacement, &mut out); } else { out.push_str(replacement); }
+            (c.get(0).unwrap().range(), out)
+        }).collect()
+    }
+
+    /// Match spans in `line` as char offsets into its trimmed form, which is what results display.
+    pub fn ranges(&self, line: &str) -> Vec<MatchRange> {
+        let skip = line.len() - line.trim_start().len();
+        let end = skip + line.trim().len();
+        let col = |b: usize| line[skip..b.clamp(skip, end)].chars().count();
+        self.re.find_iter(line).filter(|m| m.end() > m.start()).map(|m| MatchRange { start: col(m.start()), end: col(m.end()) }).collect()
+    }
+}
+
+/// Smart-case trigger: an uppercase letter that is not part of a regex escape like `\W` or `\S`.
+fn has_uppercase(query: &str, regex: bool) -> bool {
+    let mut escaped = false;
+    for c in query.chars() {
+        if regex && escaped { escaped = false; continue; }
+        if regex && c == '\\' { escaped = true; continue; }
+        if c.is_uppercase() { return true; }
+    }
+    false
+}
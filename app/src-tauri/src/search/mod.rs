@@ -1,6 +1,16 @@
 
 pub mod models;
 pub mod commands;
+pub mod matcher;
+pub mod engine;
+pub mod replace;
+pub mod saved;
+pub mod structural;
 
-pub use models::SearchResult;
-pub use commands::search_in_projects;
+pub use models::{MatchRange, SearchResult};
+pub use matcher::Matcher;
+pub use commands::{search_in_projects, start_search, cancel_search};
+pub use engine::SearchProgress;
+pub use replace::{preview_replace, apply_replace, undo_replace};
+pub use structural::{structural_search, preview_structural_rewrite, apply_structural_rewrite};
+pub use saved::{list_search_history, clear_search_history, save_search, list_saved_searches, delete_saved_search, run_saved_search, watch_saved_search, unwatch_saved_search};
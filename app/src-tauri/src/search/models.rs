@@ -5,5 +5,20 @@ use serde::{Deserialize, Serialize};
 pub struct SearchResult {
     pub path: String,
     pub line: usize,
+    /// Last line of the match; differs from `line` only for multiline matches.
+    pub end_line: usize,
     pub content: String,
+    /// Highlight spans within `content`, as char offsets.
+    pub matches: Vec<MatchRange>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    pub encoding: String,
+    /// Set for a single "binary file matches" entry; `content` is empty then.
+    pub binary: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
 }
@@ -0,0 +1,228 @@
+
+use crate::fs::encoding::{decode_text, encode_text};
+use crate::fs::{content_hash, write_atomic};
+use crate::search::commands::SearchOptions;
+use crate::search::engine::{search_files, SearchStats};
+use crate::search::matcher::Matcher;
+use crate::state::{AppState, IndexMap};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+const CONTEXT: usize = 3;
+
+#[derive(Serialize)]
+pub struct ReplacePreview {
+    pub path: String,
+    /// Content hash at preview time; `apply_replace` refuses the file if it no longer matches.
+    pub hash: String,
+    pub replacements: usize,
+    pub diff: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReplaceTarget {
+    pub path: String,
+    pub hash: String,
+}
+
+#[derive(Serialize)]
+pub struct ReplaceBatch {
+    pub id: String,
+    pub files: Vec<String>,
+    pub replacements: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BatchFile {
+    path: String,
+    backup: PathBuf,
+    hash: String,
+}
+
+struct Rewrite {
+    text: String,
+    count: usize,
+    old: Vec<String>,
+    changes: Vec<Change>,
+}
+
+/// Replaces `old_len` lines starting at line `start` (0-based) with `new`.
+pub(crate) struct Change {
+    pub start: usize,
+    pub old_len: usize,
+    pub new: Vec<String>,
+}
+
+fn body(line: &str) -> &str { line.trim_end_matches(['\r', '\n']) }
+
+/// Replaces line by line, like the search matches, keeping each line's terminator. A multiline
+/// matcher runs on the whole text instead, as the search does.
+fn rewrite(content: &str, m: &Matcher, replacement: &str) -> Option<Rewrite> {
+    if m.multiline { return rewrite_multiline(content, m, replacement); }
+    let (mut text, mut count, mut old, mut changes) = (String::with_capacity(content.len()), 0, Vec::new(), Vec::new());
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let line = body(raw);
+        let (out, n) = m.replace(line, replacement);
+        text.push_str(&out);
+        text.push_str(&raw[line.len()..]);
+        count += n;
+        if out != line { changes.push(Change { start: i, old_len: 1, new: out.split('\n').map(String::from).collect() }); }
+        old.push(line.to_string());
+    }
+    (count > 0).then_some(Rewrite { text, count, old, changes })
+}
+
+/// Whole-text replace; each change spans the lines from a match's start to its end, merged where matches share a line.
+fn rewrite_multiline(content: &str, m: &Matcher, replacement: &str) -> Option<Rewrite> {
+    let reps = m.replacements(content, replacement);
+    if reps.is_empty() { return None; }
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let starts: Vec<usize> = lines.iter().scan(0, |off, l| { let s = *off; *off += l.len(); Some(s) }).collect();
+    let line_of = |off: usize| starts.partition_point(|&x| x <= off).max(1) - 1;
+    let line_end = |l: usize| starts.get(l + 1).copied().unwrap_or(content.len());
+    let mut groups: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+    for (i, (r, _)) in reps.iter().enumerate() {
+        // a match that consumes a line break joins the following line into the change
+        let (sl, el) = (line_of(r.start), line_of(r.end));
+        match groups.last_mut() {
+            Some(g) if sl <= g.1 => { g.1 = g.1.max(el); g.2.push(i); }
+            _ => groups.push((sl, el, vec![i])),
+        }
+    }
+    let mut text = String::with_capacity(content.len());
+    let mut last = 0;
+    for (r, rep) in &reps {
+        text.push_str(&content[last..r.start]);
+        text.push_str(rep);
+        last = r.end;
+    }
+    text.push_str(&content[last..]);
+    let changes = groups.into_iter().map(|(sl, el, hits)| {
+        let (start, end) = (starts.get(sl).copied().unwrap_or(0), line_end(el));
+        let mut new = content[start..end].to_string();
+        for &i in hits.iter().rev() { new.replace_range(reps[i].0.start - start..reps[i].0.end - start, &reps[i].1); }
+        Change { start: sl, old_len: (el + 1).min(lines.len()).saturating_sub(sl), new: new.split_inclusive('\n').map(|l| body(l).to_string()).collect() }
+    }).collect();
+    Some(Rewrite { text, count: reps.len(), old: lines.iter().map(|l| body(l).to_string()).collect(), changes })
+}
+
+/// Unified diff of sorted, non-overlapping line changes, with `CONTEXT` lines around each hunk.
+pub(crate) fn unified_diff(path: &str, old: &[String], changes: &[Change]) -> String {
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let (mut i, mut delta) = (0, 0isize);
+    while i < changes.len() {
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1].start - (changes[j].start + changes[j].old_len) <= CONTEXT * 2 { j += 1; }
+        let (start, end) = (changes[i].start.saturating_sub(CONTEXT), (changes[j].start + changes[j].old_len + CONTEXT).min(old.len()));
+        let (mut body, mut new_len, mut k) = (String::new(), 0, start);
+        for c in &changes[i..=j] {
+            for l in &old[k..c.start] { body.push_str(&format!(" {}\n", l)); new_len += 1; }
+            for l in &old[c.start..c.start + c.old_len] { body.push_str(&format!("-{}\n", l)); }
+            for l in &c.new { body.push_str(&format!("+{}\n", l)); new_len += 1; }
+            k = c.start + c.old_len;
+        }
+        for l in &old[k..end] { body.push_str(&format!(" {}\n", l)); new_len += 1; }
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n{}", start + 1, end - start, start as isize + delta + 1, new_len, body));
+        delta += new_len as isize - (end - start) as isize;
+        i = j + 1;
+    }
+    out
+}
+
+fn candidates(paths: &[String], opts: &SearchOptions, m: &Matcher, indexes: &IndexMap) -> Result<Vec<String>, String> {
+    let found = Mutex::new(BTreeSet::new());
+    search_files(paths, opts, m, indexes, &AtomicBool::new(false), &SearchStats::default(), &|hits| {
+        if let Some(h) = hits.first() { found.lock().unwrap().insert(h.path.clone()); }
+        true
+    })?;
+    Ok(found.into_inner().unwrap().into_iter().collect())
+}
+
+#[tauri::command]
+pub async fn preview_replace(state: tauri::State<'_, AppState>, paths: Vec<String>, query: String, options: SearchOptions, replacement: String) -> Result<Vec<ReplacePreview>, String> {
+    if query.len() < 2 { return Ok(vec![]); }
+    let matcher = Matcher::new(&query, &options)?;
+    let indexes = state.indexes.lock().unwrap().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut res = Vec::new();
+        for path in candidates(&paths, &options, &matcher, &indexes)? {
+            let Ok(bytes) = fs::read(&path) else { continue };
+            // binary, or bytes that would not survive re-encoding
+            let Some(doc) = decode_text(&bytes).filter(|d| !d.malformed) else { continue };
+            let Some(rw) = rewrite(&doc.text, &matcher, &replacement) else { continue };
+            if encode_text(&rw.text, doc.encoding, doc.bom).is_err() { continue; }
+            res.push(ReplacePreview { hash: content_hash(&bytes), replacements: rw.count, diff: unified_diff(&path, &rw.old, &rw.changes), path });
+        }
+        Ok(res)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Rewrites every target or none: all hashes are checked first, originals are backed up, and a failed
+/// write rolls back the files already written. The returned batch id can be passed to `undo_replace`.
+#[tauri::command]
+pub async fn apply_replace(state: tauri::State<'_, AppState>, query: String, options: SearchOptions, replacement: String, files: Vec<ReplaceTarget>) -> Result<ReplaceBatch, String> {
+    let matcher = Matcher::new(&query, &options)?;
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut planned, mut stale) = (Vec::new(), Vec::new());
+        for t in files {
+            let bytes = fs::read(&t.path).map_err(|e| format!("{}: {}", t.path, e))?;
+            if content_hash(&bytes) != t.hash { stale.push(t.path); continue; }
+            let doc = decode_text(&bytes).filter(|d| !d.malformed).ok_or_else(|| format!("{}: not a text file", t.path))?;
+            if let Some(rw) = rewrite(&doc.text, &matcher, &replacement) {
+                let new = encode_text(&rw.text, doc.encoding, doc.bom).map_err(|e| format!("{}: {}", t.path, e))?;
+                planned.push((t.path, bytes, new, rw.count));
+            }
+        }
+        if !stale.is_empty() { return Err(format!("Files changed since the preview: {}", stale.join(", "))); }
+        commit_batch(&db, &data_dir, planned)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Writes `(path, old bytes, new bytes, replacements)` rewrites as one undoable batch; see `apply_replace`.
+pub(crate) fn commit_batch(db: &Mutex<Connection>, data_dir: &Path, planned: Vec<(String, Vec<u8>, Vec<u8>, usize)>) -> Result<ReplaceBatch, String> {
+    let id = format!("{:x}", chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default());
+    let dir = data_dir.join("replace_backups").join(&id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut record = Vec::new();
+    for (i, (path, old, new, _)) in planned.iter().enumerate() {
+        let backup = dir.join(i.to_string());
+        fs::write(&backup, old).map_err(|e| e.to_string())?;
+        record.push(BatchFile { path: path.clone(), backup, hash: content_hash(new) });
+    }
+    for (i, (path, _, new, _)) in planned.iter().enumerate() {
+        if let Err(e) = write_atomic(Path::new(path), new) {
+            for (p, old, _, _) in &planned[..i] { let _ = write_atomic(Path::new(p), old); }
+            let _ = fs::remove_dir_all(&dir);
+            return Err(format!("{}: {}", path, e));
+        }
+    }
+
+    let files_json = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+    db.lock().unwrap().execute("INSERT INTO replace_batches (id, created_at, files) VALUES (?, ?, ?)", params![id, chrono::Utc::now().timestamp_millis(), files_json]).map_err(|e| e.to_string())?;
+    Ok(ReplaceBatch { id, replacements: planned.iter().map(|p| p.3).sum(), files: record.into_iter().map(|r| r.path).collect() })
+}
+
+/// Restores the originals of a replace batch, refusing if any file was edited after the replace.
+#[tauri::command]
+pub async fn undo_replace(state: tauri::State<'_, AppState>, batch_id: String) -> Result<Vec<String>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let files: Option<String> = db.lock().unwrap().query_row("SELECT files FROM replace_batches WHERE id = ?", params![batch_id], |r| r.get(0)).optional().map_err(|e| e.to_string())?;
+        let record: Vec<BatchFile> = serde_json::from_str(&files.ok_or_else(|| format!("Unknown replace batch: {}", batch_id))?).map_err(|e| e.to_string())?;
+        let stale: Vec<&str> = record.iter().filter(|f| fs::read(&f.path).map(|b| content_hash(&b) != f.hash).unwrap_or(true)).map(|f| f.path.as_str()).collect();
+        if !stale.is_empty() { return Err(format!("Files changed since the replace: {}", stale.join(", "))); }
+        for f in &record {
+            let old = fs::read(&f.backup).map_err(|e| e.to_string())?;
+            write_atomic(Path::new(&f.path), &old).map_err(|e| format!("{}: {}", f.path, e))?;
+        }
+        db.lock().unwrap().execute("DELETE FROM replace_batches WHERE id = ?", params![batch_id]).map_err(|e| e.to_string())?;
+        if let Some(dir) = record.first().and_then(|f| f.backup.parent()) { let _ = fs::remove_dir_all(dir); }
+        Ok(record.into_iter().map(|f| f.path).collect())
+    }).await.map_err(|e| e.to_string())?
+}
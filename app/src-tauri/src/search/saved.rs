@@ -0,0 +1,196 @@
+
+use crate::search::commands::{collect_up_to, SearchOptions, MAX_RESULTS};
+use crate::search::matcher::Matcher;
+use crate::search::models::SearchResult;
+use crate::state::AppState;
+use crate::watcher::watch_debounced;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const HISTORY_LIMIT: i64 = 200;
+const WATCH_INTERVAL: Duration = Duration::from_millis(1000);
+/// Watched searches with more hits than this are not diffed.
+const WATCH_LIMIT: usize = 10_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    pub options: SearchOptions,
+    pub paths: Vec<String>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub watching: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub query: String,
+    pub options: SearchOptions,
+    pub paths: Vec<String>,
+    pub searched_at: i64,
+}
+
+/// Hits that appeared or disappeared since the previous run of a watched search.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchDiff {
+    pub name: String,
+    pub added: Vec<SearchResult>,
+    pub removed: Vec<SearchResult>,
+    /// Set when the search crossed the watch limit, in either direction, or was redefined; the
+    /// lists are then empty and the search should be re-run instead.
+    pub truncated: bool,
+}
+
+fn watcher_id(name: &str) -> String { format!("saved-search:{}", name) }
+
+/// Moves the search to the top of the history, dropping the oldest entries beyond the limit.
+pub(crate) fn record_history(db: &Mutex<Connection>, query: &str, options: &SearchOptions, paths: &[String]) {
+    if query.len() < 2 { return; }
+    let (Ok(opts), Ok(paths)) = (serde_json::to_string(options), serde_json::to_string(paths)) else { return };
+    let conn = db.lock().unwrap();
+    let _ = conn.execute("DELETE FROM search_history WHERE query = ? AND options = ? AND paths = ?", params![query, opts, paths]);
+    let _ = conn.execute("INSERT INTO search_history (query, options, paths, searched_at) VALUES (?, ?, ?, ?)", params![query, opts, paths, chrono::Utc::now().timestamp_millis()]);
+    let _ = conn.execute("DELETE FROM search_history WHERE id NOT IN (SELECT id FROM search_history ORDER BY searched_at DESC LIMIT ?)", params![HISTORY_LIMIT]);
+}
+
+fn load_saved(conn: &Connection, name: &str) -> Result<SavedSearch, String> {
+    conn.query_row("SELECT name, query, options, paths, created_at FROM saved_searches WHERE name = ?", params![name], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?, r.get::<_, i64>(4)?))
+    }).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No saved search named {}", name))
+        .and_then(|(name, query, opts, paths, created_at)| Ok(SavedSearch {
+            name, query, created_at, watching: false,
+            options: serde_json::from_str(&opts).map_err(|e| e.to_string())?,
+            paths: serde_json::from_str(&paths).map_err(|e| e.to_string())?,
+        }))
+}
+
+/// Identifies a hit by its file, its text and how many identical lines precede it,
+/// so edits elsewhere in the file do not report it as removed and re-added.
+fn keyed(results: Vec<SearchResult>) -> HashMap<(String, String, usize), SearchResult> {
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    results.into_iter().map(|r| {
+        let n = seen.entry((r.path.clone(), r.content.clone())).or_default();
+        *n += 1;
+        ((r.path.clone(), r.content.clone(), *n), r)
+    }).collect()
+}
+
+fn diff(name: &str, old: &HashMap<(String, String, usize), SearchResult>, new: &HashMap<(String, String, usize), SearchResult>) -> SearchDiff {
+    SearchDiff {
+        name: name.to_string(),
+        truncated: false,
+        added: new.iter().filter(|(k, _)| !old.contains_key(*k)).map(|(_, r)| r.clone()).collect(),
+        removed: old.iter().filter(|(k, _)| !new.contains_key(*k)).map(|(_, r)| r.clone()).collect(),
+    }
+}
+
+fn run(app: &AppHandle, s: &SavedSearch, matcher: &Matcher, cap: usize) -> Result<(Vec<SearchResult>, bool), String> {
+    let indexes = app.state::<AppState>().indexes.lock().unwrap().clone();
+    collect_up_to(&s.paths, &s.options, matcher, &indexes, cap)
+}
+
+#[tauri::command]
+pub fn list_search_history(state: State<'_, AppState>, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let mut st = conn.prepare("SELECT id, query, options, paths, searched_at FROM search_history ORDER BY searched_at DESC LIMIT ?").map_err(|e| e.to_string())?;
+    let rows = st.query_map(params![limit.map_or(HISTORY_LIMIT, |l| l as i64)], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?, r.get::<_, i64>(4)?))
+    }).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(Result::ok).filter_map(|(id, query, opts, paths, searched_at)| Some(HistoryEntry {
+        id, query, searched_at, options: serde_json::from_str(&opts).ok()?, paths: serde_json::from_str(&paths).ok()?,
+    })).collect())
+}
+
+#[tauri::command]
+pub fn clear_search_history(state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().unwrap().execute("DELETE FROM search_history", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Saves or overwrites the search stored under `name`. A watch on an overwritten search is restarted
+/// with the new definition and announced as a truncated `saved-search-changes`.
+#[tauri::command]
+pub fn save_search(app: AppHandle, state: State<'_, AppState>, name: String, query: String, options: SearchOptions, paths: Vec<String>) -> Result<(), String> {
+    Matcher::new(&query, &options)?;
+    let (opts, paths) = (serde_json::to_string(&options).map_err(|e| e.to_string())?, serde_json::to_string(&paths).map_err(|e| e.to_string())?);
+    state.db.lock().unwrap().execute("INSERT OR REPLACE INTO saved_searches (name, query, options, paths, created_at) VALUES (?, ?, ?, ?, ?)",
+        params![name, query, opts, paths, chrono::Utc::now().timestamp_millis()]).map_err(|e| e.to_string())?;
+    if state.watchers.lock().unwrap().remove(&watcher_id(&name)).is_some() {
+        let s = load_saved(&state.db.lock().unwrap(), &name)?;
+        std::thread::spawn(move || match start_watch(&app, s) {
+            Ok(_) => { let _ = app.emit("saved-search-changes", SearchDiff { name, truncated: true, ..Default::default() }); }
+            Err(e) => eprintln!("[WARNING] Could not restart the watch on {}: {}", name, e),
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_saved_searches(state: State<'_, AppState>) -> Result<Vec<SavedSearch>, String> {
+    let names: Vec<String> = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        let mut st = conn.prepare("SELECT name FROM saved_searches ORDER BY name").map_err(|e| e.to_string())?;
+        let rows = st.query_map([], |r| r.get(0)).map_err(|e| e.to_string())?;
+        rows.filter_map(Result::ok).collect()
+    };
+    let watchers = state.watchers.lock().unwrap();
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    names.iter().map(|n| load_saved(&conn, n).map(|s| SavedSearch { watching: watchers.contains_key(&watcher_id(n)), ..s })).collect()
+}
+
+#[tauri::command]
+pub fn delete_saved_search(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    state.watchers.lock().unwrap().remove(&watcher_id(&name));
+    let n = state.db.lock().unwrap().execute("DELETE FROM saved_searches WHERE name = ?", params![name]).map_err(|e| e.to_string())?;
+    Ok(n > 0)
+}
+
+#[tauri::command]
+pub async fn run_saved_search(app: AppHandle, state: State<'_, AppState>, name: String) -> Result<Vec<SearchResult>, String> {
+    let s = load_saved(&state.db.lock().unwrap(), &name)?;
+    let matcher = Matcher::new(&s.query, &s.options)?;
+    record_history(&state.db, &s.query, &s.options, &s.paths);
+    tauri::async_runtime::spawn_blocking(move || run(&app, &s, &matcher, MAX_RESULTS).map(|(r, _)| r)).await.map_err(|e| e.to_string())?
+}
+
+/// Re-runs the saved search whenever files under its paths change and emits a `SearchDiff`
+/// as `saved-search-changes`. Resolves with the current hits.
+#[tauri::command]
+pub async fn watch_saved_search(app: AppHandle, state: State<'_, AppState>, name: String) -> Result<Vec<SearchResult>, String> {
+    let s = load_saved(&state.db.lock().unwrap(), &name)?;
+    tauri::async_runtime::spawn_blocking(move || start_watch(&app, s)).await.map_err(|e| e.to_string())?
+}
+
+/// Runs the search once and (re)places its watcher; see `watch_saved_search`.
+fn start_watch(app: &AppHandle, s: SavedSearch) -> Result<Vec<SearchResult>, String> {
+    let matcher = Matcher::new(&s.query, &s.options)?;
+    let (current, truncated) = run(app, &s, &matcher, WATCH_LIMIT)?;
+    let last = Arc::new(Mutex::new((!truncated).then(|| keyed(current.clone()))));
+    let (paths, app_c) = (s.paths.clone(), app.clone());
+    watch_debounced(app, watcher_id(&s.name), &paths, WATCH_INTERVAL, move |_| {
+        let Ok((hits, truncated)) = run(&app_c, &s, &matcher, WATCH_LIMIT) else { return };
+        let hits = (!truncated).then(|| keyed(hits));
+        let mut last = last.lock().unwrap();
+        let changes = match (last.as_ref(), hits.as_ref()) {
+            (Some(old), Some(new)) => Some(diff(&s.name, old, new)),
+            (None, None) => None,
+            _ => Some(SearchDiff { name: s.name.clone(), truncated: true, ..Default::default() }),
+        };
+        *last = hits;
+        if let Some(c) = changes.filter(|c| c.truncated || !c.added.is_empty() || !c.removed.is_empty()) { let _ = app_c.emit("saved-search-changes", c); }
+    })?;
+    Ok(current)
+}
+
+#[tauri::command]
+pub fn unwatch_saved_search(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    Ok(state.watchers.lock().unwrap().remove(&watcher_id(&name)).is_some())
+}
+
@@ -0,0 +1,366 @@
+
+use crate::fs::content_hash;
+use crate::fs::encoding::is_binary;
+use crate::search::commands::SearchOptions;
+use crate::search::engine::build_walker;
+use crate::search::replace::{commit_batch, unified_diff, Change, ReplaceBatch, ReplacePreview, ReplaceTarget};
+use crate::state::AppState;
+use crate::symbols::languages::Lang;
+use ignore::WalkState;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Mutex;
+use tree_sitter::{Node, Tree};
+
+const MAX_RESULTS: usize = 1000;
+/// Stands in for `$` where it cannot start an identifier; the grammars read `µA` as one.
+const EXPANDO: &str = "µ";
+
+#[derive(Serialize)]
+pub struct StructuralMatch {
+    pub path: String,
+    /// 1-based lines and 0-based char columns, as in `Symbol`.
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+    pub text: String,
+    /// Source text bound to each named metavariable.
+    pub captures: HashMap<String, String>,
+    pub replacement: Option<String>,
+}
+
+enum Meta { Single(String), Multi(String) }
+
+/// Byte ranges in the target bound to each metavariable.
+type Env = HashMap<String, Range<usize>>;
+
+/// A code snippet where `$NAME` matches any single node, `$$$NAME` any run of sibling nodes
+/// and `$_` / `$$$` do the same without capturing. Repeated names must bind identical text.
+struct Pattern {
+    tree: Tree,
+    source: String,
+    range: Range<usize>,
+}
+
+/// Snippets that are not valid on their own, such as Rust expressions, are retried inside these.
+fn wrappers(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::Rust => &[("", ""), ("", ";"), ("fn __pattern() { ", " }")],
+        Lang::Go => &[("", ""), ("func __pattern() { ", " }")],
+        _ => &[("", "")],
+    }
+}
+
+fn is_var_name(s: &str) -> bool { s.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') }
+
+impl Pattern {
+    fn new(pattern: &str, lang: Lang) -> Result<Self, String> {
+        let body = match lang {
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx => pattern.trim().to_string(),
+            _ => pattern.trim().replace('$', EXPANDO),
+        };
+        for (pre, post) in wrappers(lang) {
+            let source = format!("{}{}{}", pre, body, post);
+            let Some(tree) = lang.parse(&source) else { continue };
+            let range = pre.len()..pre.len() + body.len();
+            let node = tree.root_node().descendant_for_byte_range(range.start, range.end);
+            if tree.root_node().has_error() || node.is_none_or(|n| n.byte_range() != range) { continue; }
+            let pattern = Pattern { tree, source, range };
+            // it would match every node of the file, each one outermost
+            if pattern.meta(pattern.root()).is_some() { return Err("Pattern must contain code besides a metavariable".into()); }
+            return Ok(pattern);
+        }
+        Err(format!("Pattern is not valid {:?} code", lang))
+    }
+
+    fn root(&self) -> Node<'_> { self.tree.root_node().descendant_for_byte_range(self.range.start, self.range.end).unwrap() }
+
+    /// The metavariable a pattern node stands for, looking through single-child wrappers
+    /// such as the expression statement around `$$$BODY` in a block.
+    fn meta(&self, mut n: Node) -> Option<Meta> {
+        while n.child_count() == 1 { n = n.child(0)?; }
+        if n.child_count() != 0 { return None; }
+        let text = self.source[n.byte_range()].replace(EXPANDO, "$");
+        if let Some(name) = text.strip_prefix("$$$") { return is_var_name(name).then(|| Meta::Multi(name.to_string())); }
+        let name = text.strip_prefix('$')?;
+        (!name.is_empty() && is_var_name(name)).then(|| Meta::Single(name.to_string()))
+    }
+
+    fn bind(env: &mut Env, name: &str, range: Range<usize>, src: &str) -> bool {
+        if name.is_empty() || name == "_" { return true; }
+        match env.get(name) {
+            Some(r) => src[r.clone()] == src[range],
+            None => { env.insert(name.to_string(), range); true }
+        }
+    }
+
+    fn matches(&self, p: Node, t: Node, src: &str, env: &mut Env) -> bool {
+        match self.meta(p) {
+            Some(Meta::Single(name) | Meta::Multi(name)) => return Self::bind(env, &name, t.byte_range(), src),
+            None if p.child_count() == 0 => return t.child_count() == 0 && self.source[p.byte_range()] == src[t.byte_range()],
+            None => {}
+        }
+        p.kind_id() == t.kind_id() && self.matches_seq(&children(p), &children(t), src, env)
+    }
+
+    fn matches_seq(&self, ps: &[Node], ts: &[Node], src: &str, env: &mut Env) -> bool {
+        let Some((p, rest)) = ps.split_first() else { return ts.is_empty() };
+        if let Some(Meta::Multi(name)) = self.meta(*p) {
+            for k in 0..=ts.len() {
+                let mut e = env.clone();
+                let range = if k == 0 { 0..0 } else { ts[0].start_byte()..ts[k - 1].end_byte() };
+                if Self::bind(&mut e, &name, range, src) && self.matches_seq(rest, &ts[k..], src, &mut e) { *env = e; return true; }
+            }
+            return false;
+        }
+        let Some((t, trest)) = ts.split_first() else { return false };
+        let mut e = env.clone();
+        if self.matches(*p, *t, src, &mut e) && self.matches_seq(rest, trest, src, &mut e) { *env = e; return true; }
+        false
+    }
+
+    /// Outermost matches in document order; a match's own subtree is not searched again.
+    fn find(&self, tree: &Tree, src: &str) -> Vec<(Range<usize>, Env)> {
+        let (root, mut out, mut stack) = (self.root(), Vec::new(), vec![tree.root_node()]);
+        while let Some(n) = stack.pop() {
+            let mut env = Env::new();
+            if !n.is_extra() && self.matches(root, n, src, &mut env) { out.push((n.byte_range(), env)); continue; }
+            let mut c = n.walk();
+            let kids: Vec<Node> = n.children(&mut c).collect();
+            stack.extend(kids.into_iter().rev());
+        }
+        out
+    }
+}
+
+/// Children that take part in matching: comments and parser-inserted tokens are skipped.
+fn children(n: Node) -> Vec<Node> {
+    let mut c = n.walk();
+    n.children(&mut c).filter(|k| !k.is_extra() && !k.is_missing()).collect()
+}
+
+/// Fills `$NAME` and `$$$NAME` in a rewrite template; unknown names are left as written.
+fn render(template: &str, env: &Env, src: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = rest[i..].trim_start_matches('$');
+        let len = after.find(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')).unwrap_or(after.len());
+        let (sigil, name) = (&rest[i..rest.len() - after.len()], &after[..len]);
+        match env.get(name) {
+            Some(r) if !name.is_empty() => out.push_str(&src[r.clone()]),
+            _ => { out.push_str(sigil); out.push_str(name); }
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+struct FileHits {
+    path: String,
+    text: String,
+    hash: String,
+    hits: Vec<(Range<usize>, Env)>,
+}
+
+/// Patterns for the requested language; a TypeScript pattern also applies to TSX files.
+fn patterns(pattern: &str, language: &str) -> Result<HashMap<Lang, Pattern>, String> {
+    let lang = Lang::from_name(language).ok_or_else(|| format!("Unsupported language: {}", language))?;
+    let mut map = HashMap::from([(lang, Pattern::new(pattern, lang)?)]);
+    if lang == Lang::TypeScript { if let Ok(p) = Pattern::new(pattern, Lang::Tsx) { map.insert(Lang::Tsx, p); } }
+    Ok(map)
+}
+
+fn scan_file(path: &str, patterns: &HashMap<Lang, Pattern>) -> Option<FileHits> {
+    let pat = patterns.get(&Lang::from_path(Path::new(path))?)?;
+    let bytes = fs::read(path).ok()?;
+    if is_binary(&bytes) { return None; }
+    let hash = content_hash(&bytes);
+    let text = String::from_utf8(bytes).ok()?;
+    let tree = Lang::from_path(Path::new(path))?.parse(&text)?;
+    let hits = pat.find(&tree, &text);
+    (!hits.is_empty()).then_some(FileHits { path: path.to_string(), text, hash, hits })
+}
+
+fn scan(paths: &[String], options: &SearchOptions, patterns: &HashMap<Lang, Pattern>) -> Result<Vec<FileHits>, String> {
+    let files = Mutex::new(Vec::new());
+    for root in paths {
+        build_walker(root, options)?.run(|| {
+            let files = &files;
+            Box::new(move |entry| {
+                let Ok(entry) = entry else { return WalkState::Continue };
+                if entry.file_type().is_some_and(|t| t.is_file()) && Lang::from_path(entry.path()).is_some_and(|l| patterns.contains_key(&l)) {
+                    files.lock().unwrap().push(entry.path().to_string_lossy().to_string());
+                }
+                WalkState::Continue
+            })
+        });
+    }
+    let mut files = files.into_inner().unwrap();
+    files.sort();
+    Ok(files.par_iter().filter_map(|p| scan_file(p, patterns)).collect())
+}
+
+fn position(text: &str, byte: usize) -> (usize, usize) {
+    let line_start = text[..byte].rfind('\n').map_or(0, |i| i + 1);
+    (text[..byte].matches('\n').count() + 1, text[line_start..byte].chars().count())
+}
+
+/// Applies the rewrites back to front so earlier byte offsets stay valid.
+fn rewritten(f: &FileHits, template: &str) -> String {
+    let mut out = f.text.clone();
+    for (r, env) in f.hits.iter().rev() { out.replace_range(r.clone(), &render(template, env, &f.text)); }
+    out
+}
+
+/// Whole old lines touched by each rewrite, merged where rewrites share a line.
+fn line_changes(f: &FileHits, template: &str) -> (Vec<String>, Vec<Change>) {
+    let text = &f.text;
+    let old: Vec<String> = text.split_inclusive('\n').map(|l| l.trim_end_matches(['\r', '\n']).to_string()).collect();
+    let line_start = |l: usize| text.split_inclusive('\n').take(l).map(str::len).sum::<usize>();
+    let mut groups: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+    for (i, (r, _)) in f.hits.iter().enumerate() {
+        let (sl, el) = (position(text, r.start).0 - 1, position(text, r.end.max(r.start + 1) - 1).0 - 1);
+        match groups.last_mut() {
+            Some(g) if sl <= g.1 => { g.1 = g.1.max(el); g.2.push(i); }
+            _ => groups.push((sl, el, vec![i])),
+        }
+    }
+    let changes = groups.into_iter().map(|(sl, el, hits)| {
+        let (start, end) = (line_start(sl), line_start(el) + old[el].len());
+        let mut new = text[start..end].to_string();
+        for &i in hits.iter().rev() {
+            let (r, env) = &f.hits[i];
+            // a match may end on the line break, which the old line excludes
+            new.replace_range(r.start.min(end) - start..r.end.min(end) - start, &render(template, env, text));
+        }
+        Change { start: sl, old_len: el - sl + 1, new: new.split('\n').map(|l| l.trim_end_matches('\r').to_string()).collect() }
+    }).collect();
+    (old, changes)
+}
+
+/// Syntax-aware search: `pattern` is code in `language` with `$NAME` / `$$$NAME` metavariables.
+/// With a `rewrite` template each match also reports its replacement text.
+#[tauri::command]
+pub async fn structural_search(paths: Vec<String>, pattern: String, language: String, rewrite: Option<String>, options: Option<SearchOptions>) -> Result<Vec<StructuralMatch>, String> {
+    let patterns = patterns(&pattern, &language)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut res = Vec::new();
+        for f in scan(&paths, &options.unwrap_or_default(), &patterns)? {
+            for (r, env) in &f.hits {
+                if res.len() >= MAX_RESULTS { return Ok(res); }
+                let ((start_line, start_col), (end_line, end_col)) = (position(&f.text, r.start), position(&f.text, r.end));
+                res.push(StructuralMatch {
+                    path: f.path.clone(), start_line, start_col, end_line, end_col, text: f.text[r.clone()].to_string(),
+                    captures: env.iter().map(|(k, v)| (k.clone(), f.text[v.clone()].to_string())).collect(),
+                    replacement: rewrite.as_ref().map(|t| render(t, env, &f.text)),
+                });
+            }
+        }
+        Ok(res)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Same shape as `preview_replace`; the previews feed `apply_structural_rewrite`.
+#[tauri::command]
+pub async fn preview_structural_rewrite(paths: Vec<String>, pattern: String, language: String, rewrite: String, options: Option<SearchOptions>) -> Result<Vec<ReplacePreview>, String> {
+    let patterns = patterns(&pattern, &language)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        Ok(scan(&paths, &options.unwrap_or_default(), &patterns)?.into_iter().filter_map(|f| {
+            let (old, changes) = line_changes(&f, &rewrite);
+            (!changes.is_empty()).then(|| ReplacePreview { diff: unified_diff(&f.path, &old, &changes), replacements: f.hits.len(), hash: f.hash, path: f.path })
+        }).collect())
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Rewrites the previewed files as one batch that `undo_replace` can revert.
+#[tauri::command]
+pub async fn apply_structural_rewrite(state: tauri::State<'_, AppState>, pattern: String, language: String, rewrite: String, files: Vec<ReplaceTarget>) -> Result<ReplaceBatch, String> {
+    let patterns = patterns(&pattern, &language)?;
+    let (db, data_dir) = (state.db.clone(), state.data_dir.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut planned, mut stale) = (Vec::new(), Vec::new());
+        for t in files {
+            match scan_file(&t.path, &patterns) {
+                Some(f) if f.hash == t.hash => { let new = rewritten(&f, &rewrite); planned.push((f.path, f.text.into_bytes(), new.into_bytes(), f.hits.len())); }
+                Some(_) => stale.push(t.path),
+                None if fs::read(&t.path).is_ok_and(|b| content_hash(&b) != t.hash) => stale.push(t.path),
+                None => {}
+            }
+        }
+        if !stale.is_empty() { return Err(format!("Files changed since the preview: {}", stale.join(", "))); }
+        commit_batch(&db, &data_dir, planned)
+    }).await.map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(pattern: &str, src: &str) -> Vec<(Range<usize>, Env)> {
+        let tree = Lang::Rust.parse(src).unwrap();
+        Pattern::new(pattern, Lang::Rust).unwrap().find(&tree, src)
+    }
+
+    fn bound<'a>(src: &'a str, env: &Env, name: &str) -> &'a str { &src[env[name].clone()] }
+
+    #[test]
+    fn bare_metavariable_is_rejected() {
+        for p in ["$A", "$$$A", "$_"] { assert!(Pattern::new(p, Lang::Rust).is_err(), "{}", p); }
+        assert!(Pattern::new("foo($A)", Lang::Rust).is_ok());
+    }
+
+    #[test]
+    fn multi_metavariable_takes_any_run_of_siblings() {
+        let src = "fn main() { foo(1, 2, 3); foo(4); }";
+        let found = hits("foo($A, $$$REST)", src);
+        assert_eq!(found.len(), 1);
+        assert_eq!(bound(src, &found[0].1, "A"), "1");
+        assert_eq!(bound(src, &found[0].1, "REST"), "2, 3");
+        let found = hits("foo($$$ARGS)", src);
+        assert_eq!(found.iter().map(|(_, e)| bound(src, e, "ARGS")).collect::<Vec<_>>(), ["1, 2, 3", "4"]);
+    }
+
+    #[test]
+    fn repeated_names_must_bind_the_same_text() {
+        let src = "fn main() { let a = x + x; let b = x + y; }";
+        let found = hits("$A + $A", src);
+        assert_eq!(found.len(), 1);
+        assert_eq!(&src[found[0].0.clone()], "x + x");
+    }
+
+    #[test]
+    fn render_fills_known_names_only() {
+        let src = "a, b";
+        let env = Env::from([("A".to_string(), 0..1), ("REST".to_string(), 3..4)]);
+        assert_eq!(render("bar($A, $$$REST)", &env, src), "bar(a, b)");
+        assert_eq!(render("$B + $ + $A", &env, src), "$B + $ + a");
+    }
+
+    #[test]
+    fn line_changes_cover_whole_lines_and_merge_shared_ones() {
+        let text = "fn main() {\n    foo(1); foo(2);\n    bar();\n    foo(3);\n}\n".to_string();
+        let found = hits("foo($A)", &text);
+        let f = FileHits { path: "a.rs".into(), hash: String::new(), hits: found, text };
+        let (old, changes) = line_changes(&f, "baz($A)");
+        assert_eq!(old.len(), 5);
+        assert_eq!(changes.iter().map(|c| (c.start, c.old_len)).collect::<Vec<_>>(), [(1, 1), (3, 1)]);
+        assert_eq!(changes[0].new, ["    baz(1); baz(2);"]);
+        assert_eq!(changes[1].new, ["    baz(3);"]);
+    }
+
+    #[test]
+    fn line_changes_clamp_a_match_ending_on_the_line_break() {
+        let f = FileHits { path: "a.rs".into(), text: "a\nfoo\nb\n".into(), hash: String::new(), hits: vec![(2..6, Env::new())] };
+        let (_, changes) = line_changes(&f, "X");
+        assert_eq!(changes.iter().map(|c| (c.start, c.old_len)).collect::<Vec<_>>(), [(1, 1)]);
+        assert_eq!(changes[0].new, ["X"]);
+    }
+}
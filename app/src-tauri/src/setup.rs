@@ -1,6 +1,7 @@
 
 use crate::state::AppState;
 use crate::{db, pty};
+use portable_pty::{Child, MasterPty};
 use std::sync::{Arc, Mutex};
 use tauri::{App, Manager, AppHandle};
 
@@ -19,26 +20,85 @@ pub fn init(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
     let sled = sled::open(dir.join("oxide_lsm_v3")).ok();
     if sled.is_none() { eprintln!("[WARNING] Sled LSM DB could not be opened (lock active?)."); }
 
+    let pty_host = pty::host::HostClient::connect_or_start(dir.join("pty-host.sock")).map_err(|e| {
+        eprintln!("[WARNING] PTY host unavailable, terminals will not survive restarts: {}", e);
+        e
+    }).ok();
+
     app.manage(AppState {
         sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
         pty_system: portable_pty::NativePtySystem::default(),
         watchers: Arc::new(Mutex::new(std::collections::HashMap::new())),
         lsm_db: Arc::new(Mutex::new(sled)),
         db: Arc::new(Mutex::new(conn)),
+        pty_host,
+        data_dir: dir.clone(),
+        searches: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        indexes: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        transfers: Arc::new(Mutex::new(std::collections::HashMap::new())),
     });
+    restore_sessions(app.handle());
+    crate::index::restore_indexes(app.handle());
+    crate::symbols::restore_symbols(app.handle());
     Ok(())
 }
 
-#[tauri::command]
-pub fn spawn_pty(app: AppHandle, id: String, cwd: Option<String>, rows: u16, cols: u16) -> Result<bool, String> {
+/// Flushes buffered scrollback before the app exits; hosted sessions keep running without it.
+pub fn shutdown(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    for s in state.sessions.lock().unwrap().values() { s.spool.lock().unwrap().flush(); }
+}
+
+/// Re-attaches to every PTY the host kept alive while the app was closed.
+fn restore_sessions(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let dir = pty::scrollback::spool_dir(&state.data_dir);
+    let Some(host) = state.pty_host.clone() else { return pty::scrollback::prune_spools(&dir, &[]) };
+    for s in host.list().unwrap_or_default() {
+        if !s.alive { let _ = host.request(&pty::host::protocol::HostRequest::Kill { id: s.id }); continue; }
+        match host.attach(&s.id) {
+            Ok((m, ch, replay)) => { let _ = register_session(app.clone(), s.id, m, ch, replay, false); }
+            Err(e) => eprintln!("[ERROR] Could not reattach PTY {}: {}", s.id, e),
+        }
+    }
+    let keep: Vec<_> = state.sessions.lock().unwrap().values().flat_map(|s| s.spool.lock().unwrap().files()).collect();
+    pty::scrollback::prune_spools(&dir, &keep);
+}
+
+pub fn register_session(app: AppHandle, id: String, m: Box<dyn MasterPty + Send>, ch: Box<dyn Child + Send>, replay: Vec<u8>, fresh: bool) -> Result<(), String> {
     let state = app.state::<AppState>();
-    if state.sessions.lock().unwrap().contains_key(&id) { return Ok(false); }
-    let (m, ch) = pty::spawner::create_pty(&state.pty_system, rows, cols, cwd)?;
     let reader = m.try_clone_reader().map_err(|e| e.to_string())?;
     let writer = m.take_writer().map_err(|e| e.to_string())?;
     let vis = Arc::new(Mutex::new(false));
-    let buf = Arc::new(Mutex::new(Vec::with_capacity(8192)));
-    state.sessions.lock().unwrap().insert(id.clone(), pty::models::PtySession { writer, master: m, child: ch, is_visible: vis.clone(), buffer: buf.clone() });
-    pty::reader::spawn_reader(app, id, reader, vis, buf);
+    let shell = Arc::new(Mutex::new(pty::ShellTracker::default()));
+    { let mut sh = shell.lock().unwrap(); for p in pty::markers::OscScanner::default().feed(&replay) { sh.apply(&p); } }
+    let limit = pty::scrollback::spool_limit(&state.db.lock().unwrap());
+    let mut spool = pty::Spool::open(&pty::scrollback::spool_dir(&state.data_dir), &id, limit);
+    if fresh { spool.remove(); }
+    spool.append_replay(&replay);
+    let buf = Arc::new(Mutex::new(replay));
+    let session = pty::models::PtySession { writer: Arc::new(Mutex::new(writer)), master: m, child: ch, is_visible: vis, buffer: buf, shell, spool: Arc::new(Mutex::new(spool)), recorder: Arc::new(Mutex::new(None)), output: Arc::new(pty::stream::OutputStream::default()) };
+    // inserted before the reader starts, so a child that exits at once is still found by `reap_session`
+    let mut sessions = state.sessions.lock().unwrap();
+    sessions.insert(id.clone(), session);
+    pty::reader::spawn_reader(app.clone(), id.clone(), reader, &sessions[&id]);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn spawn_pty(app: AppHandle, id: String, cwd: Option<String>, rows: u16, cols: u16, profile: Option<String>) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    if state.sessions.lock().unwrap().contains_key(&id) { return Ok(false); }
+    let prof = pty::profiles::load_profile(&state.db.lock().unwrap(), profile.as_deref().unwrap_or("default"))?;
+    let hosted = state.pty_host.as_ref().map(|h| h.spawn(&id, cwd.clone(), rows, cols, &prof));
+    let (m, ch, replay) = match hosted {
+        Some(Ok(p)) => p,
+        other => {
+            if let Some(Err(e)) = other { eprintln!("[WARNING] PTY host spawn failed, using a local session: {}", e); }
+            let (m, ch) = pty::spawner::create_pty(&state.pty_system, rows, cols, cwd, &prof, &state.data_dir)?;
+            (m, ch, Vec::with_capacity(8192))
+        }
+    };
+    register_session(app.clone(), id, m, ch, replay, true)?;
     Ok(true)
 }
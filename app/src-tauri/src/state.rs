@@ -1,11 +1,23 @@
+use crate::fs::transfer::TransferControl;
+use crate::index::ContentIndex;
+use crate::pty::host::HostClient;
 use crate::pty::PtySession;
 use notify::Watcher;
 use portable_pty::NativePtySystem;
 use rusqlite::Connection;
 use sled::Db;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
+/// Cancel flags of running streamed searches, by search id.
+pub type SearchMap = HashMap<String, Arc<AtomicBool>>;
+
+pub type IndexMap = HashMap<String, Arc<ContentIndex>>;
+
+pub type TransferMap = HashMap<String, Arc<TransferControl>>;
+
 pub type WatcherMap = HashMap<String, (Box<dyn Watcher + Send>, Vec<String>)>;
 
 pub struct AppState {
@@ -14,6 +26,11 @@ pub struct AppState {
     pub watchers: Arc<Mutex<WatcherMap>>,
     pub lsm_db: Arc<Mutex<Option<Db>>>,
     pub db: Arc<Mutex<Connection>>,
+    pub pty_host: Option<HostClient>,
+    pub data_dir: PathBuf,
+    pub searches: Arc<Mutex<SearchMap>>,
+    pub indexes: Arc<Mutex<IndexMap>>,
+    pub transfers: Arc<Mutex<TransferMap>>,
 }
 
 impl AppState {
@@ -24,6 +41,11 @@ impl AppState {
             watchers: Arc::new(Mutex::new(HashMap::new())),
             lsm_db: Arc::new(Mutex::new(None)),
             db: Arc::new(Mutex::new(db)),
+            pty_host: None,
+            data_dir: std::env::current_dir().unwrap_or_default().join("oxide_data"),
+            searches: Arc::new(Mutex::new(HashMap::new())),
+            indexes: Arc::new(Mutex::new(HashMap::new())),
+            transfers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
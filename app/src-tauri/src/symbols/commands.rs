@@ -0,0 +1,180 @@
+
+use crate::finder::fuzzy::fuzzy_match;
+use crate::fs::encoding::decode_text;
+use crate::index::store::{visible_files, visible_files_below};
+use crate::state::AppState;
+use crate::symbols::extract::{extract, Symbol};
+use crate::symbols::languages::Lang;
+use crate::watcher::watch_debounced;
+use rayon::prelude::*;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_LIMIT: usize = 100;
+/// Rows fetched by the SQL prefilter before fuzzy ranking.
+const CANDIDATE_LIMIT: i64 = 5000;
+
+#[derive(Serialize)]
+pub struct SymbolIndexStatus {
+    pub project: String,
+    pub files: usize,
+    pub symbols: usize,
+}
+
+#[derive(Serialize)]
+pub struct SymbolMatch {
+    #[serde(flatten)]
+    pub symbol: Symbol,
+    pub score: i32,
+    /// Char indices of the matched characters in `name`.
+    pub positions: Vec<usize>,
+}
+
+fn watcher_id(project: &str) -> String { format!("symbols:{}", project) }
+
+fn mtime(path: &Path) -> Option<i64> {
+    std::fs::metadata(path).ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as i64)
+}
+
+fn parse_file(path: &Path) -> Option<Vec<Symbol>> {
+    let bytes = std::fs::read(path).ok()?;
+    extract(path, &decode_text(&bytes)?.text)
+}
+
+fn store_file(conn: &Connection, project: &str, path: &str, mtime: i64, syms: &[Symbol]) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM symbols WHERE file_path = ?", params![path])?;
+    {
+        let mut st = tx.prepare_cached("INSERT INTO symbols (project_id, file_path, name, kind, container, start_line, start_col, end_line, end_col) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")?;
+        for s in syms { st.execute(params![project, path, s.name, s.kind, s.container, s.start_line, s.start_col, s.end_line, s.end_col])?; }
+    }
+    tx.execute("INSERT OR REPLACE INTO symbol_files (path, project_id, mtime) VALUES (?, ?, ?)", params![path, project, mtime])?;
+    tx.commit()
+}
+
+/// Drops the symbols of `path`, or of every file below it when it was a directory.
+fn forget(conn: &Connection, path: &str) -> rusqlite::Result<()> {
+    let below = format!("{}{}%", path, std::path::MAIN_SEPARATOR);
+    conn.execute("DELETE FROM symbols WHERE file_path = ? OR file_path LIKE ?", params![path, below])?;
+    conn.execute("DELETE FROM symbol_files WHERE path = ? OR path LIKE ?", params![path, below])?;
+    Ok(())
+}
+
+fn status(conn: &Connection, project: &str) -> Result<SymbolIndexStatus, String> {
+    let count = |sql: &str| conn.query_row(sql, params![project], |r| r.get::<_, i64>(0)).map(|n| n as usize).map_err(|e| e.to_string());
+    Ok(SymbolIndexStatus { project: project.to_string(), files: count("SELECT COUNT(*) FROM symbol_files WHERE project_id = ?")?, symbols: count("SELECT COUNT(*) FROM symbols WHERE project_id = ?")? })
+}
+
+/// Parses files below `dir` changed since the last pass and forgets the ones that disappeared or are now ignored.
+fn sync_tree(db: &Mutex<Connection>, project: &str, dir: &Path) -> Result<(), String> {
+    let below = format!("{}{}%", dir.to_string_lossy(), std::path::MAIN_SEPARATOR);
+    let mut known: HashMap<String, i64> = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let mut st = conn.prepare("SELECT path, mtime FROM symbol_files WHERE project_id = ? AND path LIKE ?").map_err(|e| e.to_string())?;
+        let rows = st.query_map(params![project, below], |r| Ok((r.get(0)?, r.get(1)?))).map_err(|e| e.to_string())?;
+        rows.filter_map(Result::ok).collect()
+    };
+    let mut changed = Vec::new();
+    for path in visible_files_below(project, dir) {
+        if Lang::from_path(&path).is_none() { continue; }
+        let (key, m) = (path.to_string_lossy().to_string(), mtime(&path).unwrap_or_default());
+        if known.remove(&key) != Some(m) { changed.push((path, m)); }
+    }
+    let parsed: Vec<(PathBuf, i64, Vec<Symbol>)> = changed.into_par_iter().filter_map(|(p, m)| parse_file(&p).map(|s| (p, m, s))).collect();
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    for (p, m, syms) in parsed { store_file(&conn, project, &p.to_string_lossy(), m, &syms).map_err(|e| e.to_string())?; }
+    for gone in known.keys() { forget(&conn, gone).map_err(|e| e.to_string())?; }
+    Ok(())
+}
+
+fn sync_project(db: &Mutex<Connection>, project: &str) -> Result<SymbolIndexStatus, String> {
+    sync_tree(db, project, Path::new(project))?;
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    status(&conn, project)
+}
+
+fn apply_changes(db: &Mutex<Connection>, project: &str, paths: Vec<PathBuf>) {
+    let mut by_dir: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for p in paths { if let Some(dir) = p.parent() { by_dir.entry(dir.to_path_buf()).or_default().push(p); } }
+    for (dir, paths) in by_dir {
+        let visible = visible_files(project, &dir);
+        for p in paths {
+            let key = p.to_string_lossy().to_string();
+            if visible.contains(&p) && Lang::from_path(&p).is_some() {
+                let Some(syms) = parse_file(&p) else { continue };
+                let _ = store_file(&db.lock().unwrap(), project, &key, mtime(&p).unwrap_or_default(), &syms);
+            } else if p.is_dir() {
+                // created, moved in, or had its ignore rules changed: catch up with what it holds now
+                let _ = sync_tree(db, project, &p);
+            } else {
+                let _ = forget(&db.lock().unwrap(), &key);
+            }
+        }
+    }
+}
+
+fn start_updates(app: &AppHandle, db: Arc<Mutex<Connection>>, project: &str) -> Result<(), String> {
+    let root = project.to_string();
+    watch_debounced(app, watcher_id(project), &[project.to_string()], UPDATE_INTERVAL, move |paths| apply_changes(&db, &root, paths))
+}
+
+/// Resumes symbol indexing for projects indexed in earlier sessions.
+pub fn restore_symbols(app: &AppHandle) {
+    let db = app.state::<AppState>().db.clone();
+    let projects: Vec<String> = {
+        let conn = db.lock().unwrap();
+        let Ok(mut st) = conn.prepare("SELECT DISTINCT project_id FROM symbol_files") else { return };
+        st.query_map([], |r| r.get(0)).map(|rows| rows.filter_map(Result::ok).collect()).unwrap_or_default()
+    };
+    for project in projects {
+        if let Err(e) = start_updates(app, db.clone(), &project) { eprintln!("[WARNING] Could not watch symbols of {}: {}", project, e); }
+        let db = db.clone();
+        std::thread::spawn(move || { let _ = sync_project(&db, &project); });
+    }
+}
+
+#[tauri::command]
+pub async fn index_symbols(app: AppHandle, state: State<'_, AppState>, project: String) -> Result<SymbolIndexStatus, String> {
+    let db = state.db.clone();
+    if !state.watchers.lock().unwrap().contains_key(&watcher_id(&project)) { start_updates(&app, db.clone(), &project)?; }
+    tauri::async_runtime::spawn_blocking(move || sync_project(&db, &project)).await.map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn document_symbols(path: String) -> Result<Vec<Symbol>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let p = Path::new(&path);
+        if Lang::from_path(p).is_none() { return Err(format!("No symbol support for {}", path)); }
+        let bytes = std::fs::read(p).map_err(|e| e.to_string())?;
+        let doc = decode_text(&bytes).ok_or("Binary file")?;
+        extract(p, &doc.text).ok_or_else(|| "Could not parse file".to_string())
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Fuzzy symbol lookup across indexed projects, or only `project` when given.
+#[tauri::command]
+pub async fn workspace_symbols(state: State<'_, AppState>, query: String, project: Option<String>, limit: Option<usize>) -> Result<Vec<SymbolMatch>, String> {
+    let q: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if q.is_empty() { return Ok(vec![]); }
+    // LIKE '%a%b%c%' is a subsequence test, a cheap superset of the fuzzy matches
+    let pattern = q.iter().fold(String::from("%"), |mut acc, c| { if matches!(c, '%' | '_' | '\\') { acc.push('\\'); } acc.push(*c); acc.push('%'); acc });
+    let rows: Vec<Symbol> = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        let mut st = conn.prepare("SELECT name, kind, container, file_path, start_line, start_col, end_line, end_col FROM symbols WHERE name LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR project_id = ?2) LIMIT ?3").map_err(|e| e.to_string())?;
+        let it = st.query_map(params![pattern, project, CANDIDATE_LIMIT], |r| Ok(Symbol {
+            name: r.get(0)?, kind: r.get(1)?, container: r.get(2)?, path: r.get(3)?,
+            start_line: r.get(4)?, start_col: r.get(5)?, end_line: r.get(6)?, end_col: r.get(7)?,
+        })).map_err(|e| e.to_string())?;
+        it.filter_map(Result::ok).collect()
+    };
+    let mut res: Vec<SymbolMatch> = rows.into_iter().filter_map(|s| fuzzy_match(&q, &s.name).map(|m| SymbolMatch { score: m.score, positions: m.positions, symbol: s })).collect();
+    res.sort_by(|a, b| b.score.cmp(&a.score).then(a.symbol.name.len().cmp(&b.symbol.name.len())));
+    res.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+    Ok(res)
+}
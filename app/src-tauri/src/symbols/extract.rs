@@ -0,0 +1,129 @@
+
+use crate::symbols::languages::Lang;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tree_sitter::{Node, QueryCursor, StreamingIterator};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: String,
+    /// Name of the enclosing symbol (impl, class, module...) or, for Go methods, the receiver type.
+    pub container: Option<String>,
+    pub path: String,
+    /// 1-based lines and 0-based char columns.
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+fn char_col(source: &str, node_byte: usize, byte_col: usize) -> usize {
+    source.get(node_byte - byte_col..node_byte).map_or(byte_col, |s| s.chars().count())
+}
+
+fn text(node: Node, source: &str) -> String {
+    node.utf8_text(source.as_bytes()).unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Outline of a source file in document order, or `None` for unsupported languages.
+pub fn extract(path: &Path, source: &str) -> Option<Vec<Symbol>> {
+    let lang = Lang::from_path(path)?;
+    let (g, tree) = (lang.grammar(), lang.parse(source)?);
+    let names = g.tags.capture_names();
+    let (mut cursor, mut seen) = (QueryCursor::new(), HashMap::new());
+    let mut defs: Vec<(usize, usize, Symbol)> = Vec::new();
+    let mut matches = cursor.matches(&g.tags, tree.root_node(), source.as_bytes());
+    while let Some(m) = matches.next() {
+        let (mut name, mut def, mut container) = (None, None, None);
+        for c in m.captures {
+            match names[c.index as usize] {
+                "name" => name = Some(c.node),
+                "container" => container = Some(text(c.node, source).trim_start_matches('*').split('[').next().unwrap_or_default().to_string()),
+                kind => def = Some((kind, c.node)),
+            }
+        }
+        let (Some(name), Some((kind, node))) = (name, def) else { continue };
+        // Earlier patterns are more specific, e.g. a Go struct before the generic type_spec,
+        // but the cursor does not promise to report them first.
+        let (s, e) = (node.start_position(), node.end_position());
+        let def = (node.start_byte(), node.end_byte(), Symbol {
+            name: text(name, source), kind: kind.to_string(), container, path: path.to_string_lossy().to_string(),
+            start_line: s.row + 1, start_col: char_col(source, node.start_byte(), s.column),
+            end_line: e.row + 1, end_col: char_col(source, node.end_byte(), e.column),
+        });
+        match seen.get(&node.id()).copied() {
+            None => { seen.insert(node.id(), (defs.len(), m.pattern_index)); defs.push(def); }
+            Some((i, pattern)) if m.pattern_index < pattern => { defs[i] = def; seen.insert(node.id(), (i, m.pattern_index)); }
+            Some(_) => {}
+        }
+    }
+    defs.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    let mut stack: Vec<(usize, String, String)> = Vec::new();
+    for (start, end, sym) in defs.iter_mut() {
+        while stack.last().is_some_and(|t| t.0 <= *start) { stack.pop(); }
+        if let Some((_, parent, parent_kind)) = stack.last() {
+            if sym.container.is_none() { sym.container = Some(parent.clone()); }
+            if sym.kind == "function" && matches!(parent_kind.as_str(), "impl" | "interface" | "class" | "struct") { sym.kind = "method".into(); }
+        }
+        stack.push((*end, sym.name.clone(), sym.kind.clone()));
+    }
+    Some(defs.into_iter().map(|d| d.2).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(file: &str, src: &str) -> Vec<(String, String, Option<String>)> {
+        extract(Path::new(file), src).unwrap().into_iter().map(|s| (s.name, s.kind, s.container)).collect()
+    }
+
+    fn sym(name: &str, kind: &str, container: Option<&str>) -> (String, String, Option<String>) {
+        (name.into(), kind.into(), container.map(String::from))
+    }
+
+    #[test]
+    fn rust_functions_in_impls_are_methods() {
+        let src = "struct Point { x: i32 }\nimpl Point {\n    fn new() -> Self { todo!() }\n}\nfn main() {}\n";
+        assert_eq!(outline("a.rs", src), [
+            sym("Point", "struct", None), sym("x", "field", Some("Point")), sym("Point", "impl", None),
+            sym("new", "method", Some("Point")), sym("main", "function", None),
+        ]);
+    }
+
+    #[test]
+    fn python_methods_take_their_class() {
+        let src = "class A:\n    def f(self):\n        pass\n\ndef g():\n    pass\n";
+        assert_eq!(outline("a.py", src), [sym("A", "class", None), sym("f", "method", Some("A")), sym("g", "function", None)]);
+    }
+
+    #[test]
+    fn go_structs_win_over_plain_types_and_methods_take_the_receiver() {
+        let src = "package p\ntype T struct{}\ntype N int\nfunc (t *T) M() {}\nfunc F() {}\n";
+        assert_eq!(outline("a.go", src), [
+            sym("T", "struct", None), sym("N", "type", None), sym("M", "method", Some("T")), sym("F", "function", None),
+        ]);
+    }
+
+    #[test]
+    fn typescript_interface_and_class_members() {
+        let src = "interface I { a(): void }\nclass C { m() {} }\nfunction f() {}\n";
+        assert_eq!(outline("a.ts", src), [
+            sym("I", "interface", None), sym("a", "method", Some("I")), sym("C", "class", None),
+            sym("m", "method", Some("C")), sym("f", "function", None),
+        ]);
+    }
+
+    #[test]
+    fn positions_are_one_based_lines_and_char_columns() {
+        let s = &extract(Path::new("a.rs"), "mod m {\n    fn é() {}\n}\n").unwrap()[1];
+        assert_eq!((s.start_line, s.start_col, s.end_line, s.end_col), (2, 4, 2, 13));
+    }
+
+    #[test]
+    fn unsupported_files_have_no_outline() {
+        assert!(extract(Path::new("notes.txt"), "fn main() {}").is_none());
+    }
+}
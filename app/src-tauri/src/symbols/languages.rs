@@ -0,0 +1,123 @@
+
+use std::path::Path;
+use std::sync::OnceLock;
+use tree_sitter::{Language, Parser, Query, Tree};
+
+/// Each pattern captures the symbol `@name`, its node under a capture named after the symbol kind,
+/// and optionally an explicit `@container` (Go method receivers).
+const RUST: &str = r#"
+(function_item name: (identifier) @name) @function
+(function_signature_item name: (identifier) @name) @function
+(struct_item name: (type_identifier) @name) @struct
+(union_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(enum_variant name: (identifier) @name) @enum_member
+(trait_item name: (type_identifier) @name) @interface
+(impl_item type: (_) @name) @impl
+(type_item name: (type_identifier) @name) @type
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+(mod_item name: (identifier) @name) @module
+(macro_definition name: (identifier) @name) @macro
+(field_declaration name: (field_identifier) @name) @field
+"#;
+
+const TYPESCRIPT: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (type_identifier) @name) @class
+(abstract_class_declaration name: (type_identifier) @name) @class
+(interface_declaration name: (type_identifier) @name) @interface
+(type_alias_declaration name: (type_identifier) @name) @type
+(enum_declaration name: (identifier) @name) @enum
+(method_definition name: (_) @name) @method
+(method_signature name: (_) @name) @method
+(abstract_method_signature name: (_) @name) @method
+(public_field_definition name: (_) @name) @field
+(property_signature name: (_) @name) @field
+(internal_module name: (_) @name) @module
+(variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)]) @function
+"#;
+
+const JAVASCRIPT: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (identifier) @name) @class
+(method_definition name: (_) @name) @method
+(field_definition property: (_) @name) @field
+(variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)]) @function
+"#;
+
+const PYTHON: &str = r#"
+(class_definition name: (identifier) @name) @class
+(function_definition name: (identifier) @name) @function
+(module (expression_statement (assignment left: (identifier) @name) @constant))
+"#;
+
+const GO: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(method_declaration receiver: (parameter_list (parameter_declaration type: (_) @container)) name: (field_identifier) @name) @method
+(type_spec name: (type_identifier) @name type: (struct_type)) @struct
+(type_spec name: (type_identifier) @name type: (interface_type)) @interface
+(type_spec name: (type_identifier) @name) @type
+(method_elem name: (field_identifier) @name) @method
+(field_declaration name: (field_identifier) @name) @field
+(const_spec name: (identifier) @name) @constant
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lang { Rust, TypeScript, Tsx, JavaScript, Python, Go }
+
+pub struct Grammar {
+    pub language: Language,
+    pub tags: Query,
+}
+
+impl Lang {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Some(match path.extension()?.to_str()? {
+            "rs" => Lang::Rust,
+            "ts" | "mts" | "cts" => Lang::TypeScript,
+            "tsx" => Lang::Tsx,
+            "js" | "mjs" | "cjs" | "jsx" => Lang::JavaScript,
+            "py" | "pyi" => Lang::Python,
+            "go" => Lang::Go,
+            _ => return None,
+        })
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Lang::Rust,
+            "typescript" | "ts" => Lang::TypeScript,
+            "tsx" => Lang::Tsx,
+            "javascript" | "js" | "jsx" => Lang::JavaScript,
+            "python" | "py" => Lang::Python,
+            "go" | "golang" => Lang::Go,
+            _ => return None,
+        })
+    }
+
+    pub fn parse(self, source: &str) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(&self.grammar().language).ok()?;
+        parser.parse(source, None)
+    }
+
+    /// Grammar and compiled tag query, built once per language.
+    pub fn grammar(self) -> &'static Grammar {
+        static GRAMMARS: [OnceLock<Grammar>; 6] = [const { OnceLock::new() }; 6];
+        GRAMMARS[self as usize].get_or_init(|| {
+            let (language, src): (Language, &str) = match self {
+                Lang::Rust => (tree_sitter_rust::LANGUAGE.into(), RUST),
+                Lang::TypeScript => (tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(), TYPESCRIPT),
+                Lang::Tsx => (tree_sitter_typescript::LANGUAGE_TSX.into(), TYPESCRIPT),
+                Lang::JavaScript => (tree_sitter_javascript::LANGUAGE.into(), JAVASCRIPT),
+                Lang::Python => (tree_sitter_python::LANGUAGE.into(), PYTHON),
+                Lang::Go => (tree_sitter_go::LANGUAGE.into(), GO),
+            };
+            let tags = Query::new(&language, src).expect("built-in tag query is valid");
+            Grammar { language, tags }
+        })
+    }
+}
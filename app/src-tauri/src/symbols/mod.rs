@@ -0,0 +1,7 @@
+
+pub mod languages;
+pub mod extract;
+pub mod commands;
+
+pub use extract::Symbol;
+pub use commands::{index_symbols, document_symbols, workspace_symbols, restore_symbols};
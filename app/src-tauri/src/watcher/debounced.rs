@@ -0,0 +1,28 @@
+
+use crate::state::AppState;
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Recursive watcher on `roots` that hands the paths changed during each `interval` to `on_batch`.
+/// It is stored under `id` in the watcher map; removing that entry stops the batching thread as well.
+pub fn watch_debounced(app: &AppHandle, id: String, roots: &[String], interval: Duration, on_batch: impl Fn(Vec<PathBuf>) + Send + 'static) -> Result<(), String> {
+    let queue = Arc::new(Mutex::new(BTreeSet::new()));
+    let weak = Arc::downgrade(&queue);
+    let mut w = RecommendedWatcher::new(move |res: notify::Result<Event>| {
+        if let Ok(event) = res { queue.lock().unwrap().extend(event.paths); }
+    }, Config::default()).map_err(|e| e.to_string())?;
+    for root in roots { w.watch(Path::new(root), RecursiveMode::Recursive).map_err(|e| e.to_string())?; }
+    app.state::<AppState>().watchers.lock().unwrap().insert(id, (Box::new(w), roots.to_vec()));
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        let Some(queue) = weak.upgrade() else { break };
+        let paths: Vec<PathBuf> = std::mem::take(&mut *queue.lock().unwrap()).into_iter().collect();
+        drop(queue);
+        if !paths.is_empty() { on_batch(paths); }
+    });
+    Ok(())
+}
@@ -1,6 +1,8 @@
 
 pub mod commands;
 pub mod folders;
+pub mod debounced;
 
 pub use commands::{watch_project, unwatch_project};
 pub use folders::{watch_folder, unwatch_folder};
+pub use debounced::watch_debounced;
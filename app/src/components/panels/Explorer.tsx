@@ -1,7 +1,7 @@
 
 import { useState, memo, useCallback, MouseEvent } from "react";
 import { useStore } from "../../store/useStore";
-import { FileEntry } from "../../store/config/types";
+import { FileEntry, TextDocument } from "../../store/config/types";
 import { TreeItemContent } from "./Explorer/components/TreeItemContent";
 import { FileContextMenu } from "./Explorer/components/FileContextMenu";
 import { useFolderManagement } from "../../hooks/index";
@@ -26,8 +26,8 @@ export const TreeItem = memo(({ entry, onClick, level = 0, projectId }: { entry:
       setFollowedFile(targetId, null, null);
     } else {
       try {
-        const content = await monitoredInvoke<string>("read_text_file", { path: item.path });
-        const progress = calculateTaskProgress(content);
+        const doc = await monitoredInvoke<TextDocument>("read_text_file", { path: item.path });
+        const progress = calculateTaskProgress(doc.text);
         setFollowedFile(targetId, item.path, progress);
       } catch (e) {
         setFollowedFile(targetId, item.path, null);
@@ -1,7 +1,7 @@
 
 import { useCallback } from "react";
 import { useStore } from "../../../store/useStore";
-import { FileEntry } from "../../../store/config/types";
+import { FileEntry, TextDocument } from "../../../store/config/types";
 import { monitoredInvoke } from "../../../utils/performance/monitoredInvoke";
 import { revealItemInDir } from "@tauri-apps/plugin-opener";
 import { t } from "../../../i18n";
@@ -35,8 +35,8 @@ export function useFileActions(projectId: string | null) {
         try {
           const lastSlash = target.path.lastIndexOf('/');
           const parent = lastSlash === -1 ? "" : target.path.substring(0, lastSlash);
-          let content = ""; if (!target.isFolder) try { content = await monitoredInvoke<string>("read_text_file", { path: target.path }); } catch {}
-          setLastDeleted({ entry: target, projectId, parentPath: parent || target.path, content });
+          let doc: TextDocument | null = null; if (!target.isFolder) try { doc = await monitoredInvoke<TextDocument>("read_text_file", { path: target.path }); } catch {}
+          setLastDeleted({ entry: target, projectId, parentPath: parent || target.path, content: doc?.text ?? "", encoding: doc?.encoding, bom: doc?.bom });
           await monitoredInvoke("delete_entry", { path: target.path });
           applyFilePatch(projectId, { parent_path: parent || target.path, removed: [target.path], added: [] });
         } catch (err) { alert(`${t('explorer.delete_failed')}: ` + err); }
@@ -2,6 +2,7 @@
 import { useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useStore } from "../../../store/useStore";
+import { TextDocument } from "../../../store/config/types";
 import { mergeTrees } from "../../../utils/tree/treeMerge";
 
 export function useWatcherActions(id: string) {
@@ -19,10 +20,9 @@ export function useWatcherActions(id: string) {
       // Reload active file content if it was changed externally
       if (changedPaths && p.selectedFile && changedPaths.includes(p.selectedFile.path)) {
         try {
-          const content = await invoke<string>("read_text_file", { path: p.selectedFile.path });
-          if (content !== null && content !== p.fileContent) {
-            updateProject(id, { fileContent: content });
-          }
+          const doc = await invoke<TextDocument>("read_text_file", { path: p.selectedFile.path });
+          const fileVersion = { mtime: doc.mtime, hash: doc.hash };
+          updateProject(id, doc.text !== p.fileContent ? { fileContent: doc.text, fileVersion } : { fileVersion });
         } catch (e) {
           // Fail silently for binary or inaccessible files
         }
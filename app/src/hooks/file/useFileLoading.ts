@@ -3,6 +3,7 @@ import { readFile } from "@tauri-apps/plugin-fs";
 import { useStore } from "../../store/useStore";
 import { isBinaryFile, getFileExtension } from "../../utils/file/fileTypeUtils";
 import { useOxideCommand } from "../state/useOxideCommand";
+import { TextDocument } from "../../store/config/types";
 
 export function useFileLoading() {
   const updateActiveProject = useStore(s => s.updateActiveProject);
@@ -15,18 +16,18 @@ export function useFileLoading() {
       const ext = getFileExtension(name);
       const mime = ext === 'pdf' ? 'application/pdf' : `image/${ext === 'svg' ? 'svg+xml' : ext}`;
       const url = URL.createObjectURL(new Blob([bytes], { type: mime }));
-      updateActiveProject({ selectedFile: f, fileContent: "", fileUrl: url });
+      updateActiveProject({ selectedFile: f, fileContent: "", fileUrl: url, fileVersion: null });
       return;
     }
 
     const size = await execute<number>("get_file_size", { path });
     if (size && size > 2 * 1024 * 1024) {
       const res = await execute<any>("read_file_mmap", { path, offset: 0, length: 50000 });
-      if (res) updateActiveProject({ selectedFile: f, fileContent: res.content, fileUrl: null });
+      if (res) updateActiveProject({ selectedFile: f, fileContent: res.content, fileUrl: null, fileVersion: null });
     } else {
-      const content = await execute<string>("read_text_file", { path });
-      if (content !== null) {
-        updateActiveProject({ selectedFile: f, fileContent: content, fileUrl: null });
+      const doc = await execute<TextDocument>("read_text_file", { path });
+      if (doc !== null) {
+        updateActiveProject({ selectedFile: f, fileContent: doc.text, fileUrl: null, fileVersion: { mtime: doc.mtime, hash: doc.hash } });
       }
     }
   }, [updateActiveProject, execute]);
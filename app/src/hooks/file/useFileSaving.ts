@@ -1,23 +1,30 @@
 import { useCallback, useRef } from "react";
 import { useStore } from "../../store/useStore";
 import { useOxideCommand } from "../state/useOxideCommand";
+import { SavedFile } from "../../store/config/types";
 
 export function useFileSaving() {
   const activeProjectId = useStore(s => s.activeProjectId);
   const activeProject = useStore(s => s.projects.find(p => p.id === activeProjectId));
   const updateActiveProject = useStore(s => s.updateActiveProject);
+  const updateProject = useStore(s => s.updateProject);
   const execute = useOxideCommand();
   const timeoutRef = useRef<any>(null);
 
   return useCallback(async (content: string) => {
     const path = activeProject?.selectedFile?.path;
-    if (!path) return;
+    if (!path || !activeProject) return;
+    const id = activeProject.id;
     
     updateActiveProject({ fileContent: content });
     if (timeoutRef.current) clearTimeout(timeoutRef.current);
     
     timeoutRef.current = setTimeout(async () => {
-      await execute("write_text_file", { path, content });
+      // read when the save runs, so a save still in flight when this was scheduled is accounted for
+      const fileVersion = useStore.getState().projects.find(p => p.id === id)?.fileVersion;
+      const options = fileVersion ? { expected_mtime: fileVersion.mtime, expected_hash: fileVersion.hash } : undefined;
+      const saved = await execute<SavedFile>("write_text_file", { path, content, options }, id);
+      if (saved) updateProject(id, { fileVersion: { mtime: saved.mtime, hash: saved.hash } });
     }, 500);
-  }, [activeProject, updateActiveProject, execute]);
+  }, [activeProject, updateActiveProject, updateProject, execute]);
 }
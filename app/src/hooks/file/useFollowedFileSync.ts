@@ -2,6 +2,7 @@ import { useEffect } from "react";
 import { useStore } from "../../store/useStore";
 import { calculateTaskProgress } from "../../utils/md/mdUtils";
 import { monitoredInvoke } from "../../utils/performance/monitoredInvoke";
+import { TextDocument } from "../../store/config/types";
 
 /**
  * Hook to automatically synchronize and calculate progress for followed files.
@@ -21,8 +22,8 @@ export function useFollowedFileSync() {
       if (project.followedFilePath && project.taskProgress === null) {
         try {
           // Check if file exists and get content via Rust command
-          const content = await monitoredInvoke<string>("read_text_file", { path: project.followedFilePath });
-          const progress = calculateTaskProgress(content);
+          const doc = await monitoredInvoke<TextDocument>("read_text_file", { path: project.followedFilePath });
+          const progress = calculateTaskProgress(doc.text);
           
           updateProject(project.id, { taskProgress: progress });
         } catch (e) {
@@ -7,7 +7,8 @@ export function useUndoOperations(lastDeleted: any, activeId: string | null, app
     if (!lastDeleted || !activeId || lastDeleted.projectId !== activeId) return;
     try {
       if (lastDeleted.entry.isFolder) await monitoredInvoke("create_dir", { path: lastDeleted.entry.path });
-      else await monitoredInvoke("write_text_file", { path: lastDeleted.entry.path, content: lastDeleted.content || "" });
+      // the file is gone, so its encoding cannot be picked up from disk again
+      else await monitoredInvoke("write_text_file", { path: lastDeleted.entry.path, content: lastDeleted.content || "", options: { encoding: lastDeleted.encoding, bom: lastDeleted.bom } });
       applyPatch(activeId, { parent_path: lastDeleted.parentPath, removed: [], added: [lastDeleted.entry] });
       setLast(null);
     } catch (e) {  }
@@ -1,6 +1,19 @@
 export interface FileEntry {
   name: string; path: string; isFolder: boolean; children?: FileEntry[] | null;
 }
+/** What `read_text_file` returns. */
+export interface TextDocument {
+  text: string; encoding: string; bom: boolean; line_ending: 'lf' | 'crlf' | 'cr' | null;
+  mixed_line_endings: boolean; binary: boolean; malformed: boolean; mtime: number; hash: string;
+}
+/** What `write_text_file` returns. */
+export interface SavedFile {
+  mtime: number; hash: string; encoding: string; bom: boolean; line_ending: 'lf' | 'crlf' | 'cr' | null;
+}
+/** The disk state the open file was read or saved at; saves are refused once the disk differs. */
+export interface FileVersion {
+  mtime: number; hash: string;
+}
 export type ViewMode = 'preview' | 'code' | 'split';
 export type TerminalTab = 'bash';
 export type ProjectStatus = 'idle' | 'working' | 'intervene';
@@ -13,7 +26,7 @@ export interface ProjectGroup {
 export interface ProjectSession {
   id: string; name: string; terminalTab: TerminalTab; status: ProjectStatus;
   selectedFile: FileEntry | null; followedFilePath: string | null;
-  taskProgress: number | null; fileContent: string; fileUrl: string | null;
+  taskProgress: number | null; fileContent: string; fileUrl: string | null; fileVersion?: FileVersion | null;
   tree: FileEntry[]; imageFiles?: FileEntry[]; isLoading?: boolean; groupId?: string;
   isGeminiActive?: boolean;
   terminalSessions?: TerminalSession[]; activeTerminalId?: string;
@@ -30,7 +43,7 @@ export interface WorkspaceState {
   restoreActiveTab: boolean; compactMode: boolean; verticalTabs: boolean;
   viewMode: ViewMode; globalTabsOrder: string[]; terminalOverviews: TerminalOverview[];
   expandedFolders: Record<string, boolean>; hydrated: boolean;
-  lastDeleted: { entry: FileEntry, projectId: string, parentPath: string, content?: string } | null;
+  lastDeleted: { entry: FileEntry, projectId: string, parentPath: string, content?: string, encoding?: string, bom?: boolean } | null;
   explorerModal: { show: boolean, type: 'file' | 'folder', target: FileEntry | null } | null;
   confirmModal: { show: boolean, title: string, message: string, onConfirm: () => void, kind?: 'danger' | 'warning' | 'info' } | null;
   promptModal: { show: boolean, title: string, label: string, defaultValue: string, onConfirm: (val: string) => void } | null;
@@ -38,7 +51,7 @@ export interface WorkspaceState {
   setExplorerModal: (modal: { show: boolean, type: 'file' | 'folder', target: FileEntry | null } | null) => void;
   setConfirmModal: (modal: { show: boolean, title: string, message: string, onConfirm: () => void, kind?: 'danger' | 'warning' | 'info' } | null) => void;
   setPromptModal: (modal: { show: boolean, title: string, label: string, defaultValue: string, onConfirm: (val: string) => void } | null) => void;
-  setLastDeleted: (data: { entry: FileEntry, projectId: string, parentPath: string, content?: string } | null) => void;
+  setLastDeleted: (data: { entry: FileEntry, projectId: string, parentPath: string, content?: string, encoding?: string, bom?: boolean } | null) => void;
   addProject: (path: string, name: string, tree: FileEntry[]) => void;
   addProjects: (items: { path: string, name: string, tree: FileEntry[] }[]) => void;
   replaceProject: (path: string, name: string, tree: FileEntry[]) => void;